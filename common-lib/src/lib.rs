@@ -1,12 +1,13 @@
 use anyhow::{bail, ensure, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use synth_8080_lib::OscType;
 pub use synth_8080_lib::{notes::Note, Float};
 
 #[cfg(feature = "bevy")]
 use bevy::prelude::*;
 
+pub mod timeline;
+
 pub type MidiNote = u8;
 pub type MidiChannel = u8;
 pub type CmdArg = u32;
@@ -29,6 +30,9 @@ pub enum MidiNoteCmd {
 pub struct RowData {
     pub notes: [Option<MidiNoteCmd>; 4],
     pub cmds: [Option<(Cmd, Option<CmdArg>)>; 2],
+    /// a midi control change, (controller, value), recorded from a midi input.
+    #[serde(default)]
+    pub cc: Option<(u8, u8)>,
 }
 
 #[cfg_attr(feature = "bevy", derive(Resource))]
@@ -37,6 +41,18 @@ pub struct Row {
     pub data: Vec<RowData>,
     pub dev: String,
     pub channel: u8,
+    /// whether the track plays through the built in synth or `dev` & `channel`.
+    #[serde(default)]
+    pub target: MidiTarget,
+    /// where the track sits in the built in synth's stereo mix, -1.0 (left) to 1.0 (right).
+    #[serde(default)]
+    pub pan: Float,
+    /// how much of the track goes to the built in synth's effects.
+    #[serde(default)]
+    pub sends: Sends,
+    /// how the track sounds on the built in synth.
+    #[serde(default)]
+    pub preset: InstrumentPreset,
 }
 
 #[cfg_attr(feature = "bevy", derive(Resource))]
@@ -44,6 +60,44 @@ pub struct Row {
 pub struct TrackerState {
     pub sequences: Vec<Row>,
     pub display_start: usize,
+    /// counts edits, so the ui can tell when it missed one.
+    #[serde(default)]
+    pub revision: u64,
+    /// the built in synth's effects & master chain.
+    #[serde(default)]
+    pub fx: FxSettings,
+    /// cells changed since the last `take_delta`.
+    #[serde(skip)]
+    changed: Vec<CellDelta>,
+    /// cc lanes changed since the last `take_delta`.
+    #[serde(skip)]
+    changed_ccs: Vec<CcDelta>,
+}
+
+/// one note column, of one row, of one track that was edited.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellDelta {
+    pub track: usize,
+    pub row: usize,
+    pub column: usize,
+    pub note: Option<MidiNoteCmd>,
+}
+
+/// the cc lane, of one row, of one track that was edited.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CcDelta {
+    pub track: usize,
+    pub row: usize,
+    pub cc: Option<(u8, u8)>,
+}
+
+/// the cells changed by an edit, and the revision of the song after it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDelta {
+    pub revision: u64,
+    pub cells: Vec<CellDelta>,
+    #[serde(default)]
+    pub ccs: Vec<CcDelta>,
 }
 
 impl Default for TrackerState {
@@ -58,6 +112,10 @@ impl Default for TrackerState {
             data,
             dev: DEFAULT_MIDI_DEV_NAME.into(),
             channel: 0,
+            target: MidiTarget::default(),
+            pan: 0.0,
+            sends: Sends::default(),
+            preset: InstrumentPreset::default(),
         };
 
         Self {
@@ -97,6 +155,10 @@ impl Default for TrackerState {
             .into_iter()
             .collect(),
             display_start: 0,
+            revision: 0,
+            fx: FxSettings::default(),
+            changed: Vec::new(),
+            changed_ccs: Vec::new(),
         }
     }
 }
@@ -133,11 +195,103 @@ impl TrackerState {
             }
         }
 
-        self.sequences[channel].data[row].notes[note_num] = note;
+        self.set_note(channel, row, note_num, note);
 
         Ok(())
     }
 
+    /// sets a note column, remembering it for the next delta if it changed.
+    fn set_note(&mut self, track: usize, row: usize, column: usize, note: Option<MidiNoteCmd>) {
+        let cell = &mut self.sequences[track].data[row].notes[column];
+
+        if *cell != note {
+            *cell = note;
+            self.changed.push(CellDelta {
+                track,
+                row,
+                column,
+                note,
+            });
+        }
+    }
+
+    /// sets the cc lane of `row`, remembering it for the next delta if it changed.
+    pub fn set_cc(
+        &mut self,
+        channel: ChannelIndex,
+        row: usize,
+        cc: Option<(u8, u8)>,
+    ) -> Result<()> {
+        let track = self.channel_len_check(channel)?;
+        ensure!(
+            row < self.sequences[track].data.len(),
+            "row {row} is past the end of the song"
+        );
+
+        let lane = &mut self.sequences[track].data[row].cc;
+
+        if *lane != cc {
+            *lane = cc;
+            self.changed_ccs.push(CcDelta { track, row, cc });
+        }
+
+        Ok(())
+    }
+
+    /// the cells edited since the last call. the revision only goes up if something changed.
+    pub fn take_delta(&mut self) -> StateDelta {
+        if !self.changed.is_empty() || !self.changed_ccs.is_empty() {
+            self.revision += 1;
+        }
+
+        StateDelta {
+            revision: self.revision,
+            cells: std::mem::take(&mut self.changed),
+            ccs: std::mem::take(&mut self.changed_ccs),
+        }
+    }
+
+    /// applies a delta to a copy of the song that starts at `start_row` (see `copy_from_row`).
+    /// returns false when an earlier delta was missed, and the copy has to be fetched again.
+    pub fn apply_delta(&mut self, start_row: usize, delta: &StateDelta) -> bool {
+        if delta.revision <= self.revision {
+            // already applied, it came with a newer copy, or as both the event and the edit's reply.
+            return true;
+        }
+
+        if delta.revision != self.revision + 1 {
+            return false;
+        }
+
+        for cell in delta.cells.iter() {
+            let Some(row) = cell
+                .row
+                .checked_sub(start_row)
+                .and_then(|row| self.sequences.get_mut(cell.track)?.data.get_mut(row))
+            else {
+                continue;
+            };
+
+            row.notes[cell.column] = cell.note;
+        }
+
+        for lane in delta.ccs.iter() {
+            let Some(row) = lane
+                .row
+                .checked_sub(start_row)
+                .and_then(|row| self.sequences.get_mut(lane.track)?.data.get_mut(row))
+            else {
+                continue;
+            };
+
+            row.cc = lane.cc;
+        }
+
+        self.revision = delta.revision;
+
+        true
+    }
+
     pub fn rm_note(&mut self, channel: ChannelIndex, row: usize, note_num: usize) -> Result<()> {
         ensure!(note_num < 4, "lines can only have 4 notes per line");
 
@@ -157,12 +311,12 @@ impl TrackerState {
         while Some(MidiNoteCmd::HoldNote) == self.sequences[channel].data[i].notes[note_num]
             || i == row
         {
-            self.sequences[channel].data[i].notes[note_num] = None;
+            self.set_note(channel, i, note_num, None);
 
             i += 1;
         }
 
-        self.sequences[channel].data[i].notes[note_num] = None;
+        self.set_note(channel, i, note_num, None);
 
         if row > 0 {
             let mut i = row - 1;
@@ -170,7 +324,7 @@ impl TrackerState {
             while Some(MidiNoteCmd::HoldNote) == self.sequences[channel].data[i].notes[note_num]
                 || i == row - 1
             {
-                self.sequences[channel].data[i].notes[note_num] = None;
+                self.set_note(channel, i, note_num, None);
 
                 if i == 0 {
                     break;
@@ -179,7 +333,7 @@ impl TrackerState {
                 i -= 1;
             }
 
-            self.sequences[channel].data[i].notes[note_num] = None;
+            self.set_note(channel, i, note_num, None);
         }
 
         Ok(())
@@ -192,6 +346,10 @@ impl TrackerState {
             data,
             dev: DEFAULT_MIDI_DEV_NAME.into(),
             channel: 0,
+            target: MidiTarget::default(),
+            pan: 0.0,
+            sends: Sends::default(),
+            preset: InstrumentPreset::default(),
         };
 
         Self {
@@ -216,6 +374,10 @@ impl TrackerState {
             .into_iter()
             .collect(),
             display_start: 0,
+            revision: 0,
+            fx: FxSettings::default(),
+            changed: Vec::new(),
+            changed_ccs: Vec::new(),
         }
     }
 
@@ -250,27 +412,53 @@ impl TrackerState {
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub enum PlaybackCmd {
+    /// resumes from where playback was paused or positioned.
     Play,
     Pause,
+    /// stops playback and rewinds to the start of the song.
     Stop,
+    /// plays from the start of the song, even while playing.
     Restart,
+    /// moves the playhead without changing if it's playing.
     SetCursor(usize),
+    /// starts playing from the given row (the editors cursor).
+    PlayFrom(usize),
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PlaybackState {
     /// holds the current row
     Playing(usize),
     /// holds the row where playback is paused
     Paused(usize),
-    NotPlaying,
+    /// holds the row playback will start from
+    Stopped(usize),
 }
 
-// #[derive(Serialize, Deserialize, Clone, Debug)]
-// pub enum MidiTarget {
-//     BuiltinSynth,
-//     MidiOut,
-// }
+impl Default for PlaybackState {
+    fn default() -> Self {
+        Self::Stopped(0)
+    }
+}
+
+impl PlaybackState {
+    /// the row of the playhead.
+    pub fn row(&self) -> usize {
+        match *self {
+            Self::Playing(row) | Self::Paused(row) | Self::Stopped(row) => row,
+        }
+    }
+}
+
+/// where a track sends its notes.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MidiTarget {
+    /// the synth built into the tracker, so it makes sound with nothing plugged in.
+    #[default]
+    BuiltinSynth,
+    /// the tracks midi device & channel.
+    MidiOut,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum Channel {
@@ -278,10 +466,692 @@ pub enum Channel {
     SomeChannels(Vec<ChannelIndex>),
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// the shapes the built in synth's oscillators can make.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+}
+
+/// an amplitude envelope for the built in synth. times are in seconds, sustain is a level from
+/// 0.0 to 1.0.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Adsr {
+    pub attack: Float,
+    pub decay: Float,
+    pub sustain: Float,
+    pub release: Float,
+}
+
+impl Default for Adsr {
+    fn default() -> Self {
+        Self {
+            attack: 0.005,
+            decay: 0.1,
+            sustain: 0.8,
+            release: 0.2,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    #[default]
+    LowPass,
+    HighPass,
+    BandPass,
+}
+
+/// the resonant filter of a built in synth track.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FilterSettings {
+    pub mode: FilterMode,
+    /// in Hz.
+    pub cutoff: Float,
+    /// 0.0 to 1.0, it rings near 1.0.
+    pub resonance: Float,
+    /// how many octaves the cutoff is moved by `env` at its peak, can be negative.
+    pub env_amount: Float,
+    /// how much the cutoff follows the note played, 1.0 moves it an octave per octave from C4.
+    pub key_track: Float,
+    pub env: Adsr,
+}
+
+impl Default for FilterSettings {
+    fn default() -> Self {
+        Self {
+            mode: FilterMode::default(),
+            cutoff: 20_000.0,
+            resonance: 0.0,
+            env_amount: 0.0,
+            key_track: 0.0,
+            env: Adsr::default(),
+        }
+    }
+}
+
+/// whether a built in synth track plays chords or one note at a time.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VoiceMode {
+    #[default]
+    Poly,
+    /// one note at a time, the last note held is the one that plays.
+    Mono,
+}
+
+/// what happens to a note that's played while the track is already sounding it (or, in mono,
+/// any note).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NoteTrigger {
+    /// the envelopes start again.
+    #[default]
+    Retrigger,
+    /// the envelopes carry on, only the pitch changes.
+    Legato,
+}
+
+/// which voice is cut short when a note needs one and they're all in use. voices that are
+/// releasing always go first.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StealMode {
+    #[default]
+    Oldest,
+    Quietest,
+}
+
+/// how a built in synth track hands out its voices.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoiceSettings {
+    pub mode: VoiceMode,
+    /// how many voices can sound at once, counting ones that are releasing.
+    pub polyphony: usize,
+    pub trigger: NoteTrigger,
+    pub steal: StealMode,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            mode: VoiceMode::default(),
+            polyphony: 8,
+            trigger: NoteTrigger::default(),
+            steal: StealMode::default(),
+        }
+    }
+}
+
+/// when the pitch of a built in synth track slides from one note to the next.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GlideMode {
+    /// every note slides from the one before it.
+    #[default]
+    Always,
+    /// only notes played while the one before is still held slide, like a 303.
+    Legato,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct GlideSettings {
+    /// how long a slide takes in seconds, 0.0 turns glide off.
+    pub time: Float,
+    pub mode: GlideMode,
+}
+
+impl GlideSettings {
+    /// how long a note slides for, `None` if it doesn't. `legato` is whether the note before is
+    /// still held.
+    pub fn slide_time(&self, legato: bool) -> Option<Float> {
+        (self.time > 0.0 && (legato || self.mode == GlideMode::Always)).then_some(self.time)
+    }
+}
+
+/// how a note's velocity is shaped before it's used.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VelocityCurve {
+    #[default]
+    Linear,
+    /// loud notes are easier to play.
+    Soft,
+    /// loud notes take a harder hit.
+    Hard,
+    /// every note plays at full velocity.
+    Fixed,
+}
+
+impl VelocityCurve {
+    /// shapes a midi velocity (0 to 127) into 0.0 to 1.0.
+    pub fn apply(self, velocity: u8) -> Float {
+        let x = velocity.min(127) as Float / 127.0;
+
+        match self {
+            Self::Linear => x,
+            Self::Soft => x.sqrt(),
+            Self::Hard => x * x,
+            Self::Fixed => 1.0,
+        }
+    }
+}
+
+/// what the velocity of a note does on a built in synth track.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct VelocitySettings {
+    pub curve: VelocityCurve,
+    /// how much velocity changes the volume, 0.0 (not at all) to 1.0 (silent at velocity 0).
+    pub amp: Float,
+    /// how many octaves the filter cutoff is raised by a note at full velocity, can be negative.
+    pub cutoff: Float,
+}
+
+impl Default for VelocitySettings {
+    fn default() -> Self {
+        Self {
+            curve: VelocityCurve::default(),
+            amp: 1.0,
+            cutoff: 0.0,
+        }
+    }
+}
+
+impl VelocitySettings {
+    /// the volume & cutoff shift, in octaves, of a note played at `velocity`.
+    pub fn apply(&self, velocity: u8) -> (Float, Float) {
+        let shaped = self.curve.apply(velocity);
+        let amp = self.amp.clamp(0.0, 1.0);
+
+        (1.0 - amp + amp * shaped, self.cutoff * shaped)
+    }
+}
+
+/// how many lfos each built in synth track has.
+pub const N_LFOS: usize = 2;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    Square,
+    Saw,
+    /// a new random level every cycle.
+    SampleHold,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LfoRate {
+    /// free running, in cycles per second.
+    Hz(Float),
+    /// synced to the tempo, one cycle every so many beats.
+    Beats(Float),
+}
+
+impl Default for LfoRate {
+    fn default() -> Self {
+        Self::Hz(1.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct LfoSettings {
+    pub shape: LfoShape,
+    pub rate: LfoRate,
+}
+
+/// something that moves over time and can be routed to a `ModTarget`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModSource {
+    /// one of the tracks lfos, -1.0 to 1.0.
+    Lfo(usize),
+    /// the amplitude envelope of each voice, 0.0 to 1.0.
+    AmpEnv,
+    /// the filter envelope of each voice, 0.0 to 1.0.
+    FilterEnv,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModTarget {
+    /// in semitones.
+    Pitch,
+    /// a fraction of the volume, added on top of it.
+    Amp,
+    /// -1.0 to 1.0, added to the tracks pan.
+    Pan,
+    /// in octaves.
+    Cutoff,
+    /// added to the wavetable position.
+    WavetablePos,
+}
+
+/// a connection in the modulation matrix. the source is scaled by `amount` (in the units of the
+/// target) and added to the target.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ModRoute {
+    pub source: ModSource,
+    pub target: ModTarget,
+    pub amount: Float,
+}
+
+/// the lfos of a built in synth track and where they, and the envelopes, are routed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ModMatrix {
+    pub lfos: [LfoSettings; N_LFOS],
+    pub routes: Vec<ModRoute>,
+}
+
+/// everything about how a built in synth track sounds. it's saved with the song, and can be saved
+/// to its own file to use in other songs.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct InstrumentPreset {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub instrument: Instrument,
+    #[serde(default)]
+    pub wavetable: Wavetable,
+    #[serde(default)]
+    pub wavetable_pos: Float,
+    #[serde(default)]
+    pub envelope: Adsr,
+    #[serde(default)]
+    pub filter: FilterSettings,
+    #[serde(default)]
+    pub velocity: VelocitySettings,
+    #[serde(default)]
+    pub voices: VoiceSettings,
+    #[serde(default)]
+    pub glide: GlideSettings,
+    #[serde(default)]
+    pub modulation: ModMatrix,
+}
+
+/// how much of a track is sent to each of the built in synth's effects, 0.0 to 1.0.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sends {
+    pub delay: Float,
+    pub reverb: Float,
+    pub chorus: Float,
+}
+
+/// an echo, synced to the tempo.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct DelaySettings {
+    /// the time between echoes in beats, 0.75 is a dotted eighth.
+    pub beats: Float,
+    /// how much of each echo is fed back into the next, 0.0 to just under 1.0.
+    pub feedback: Float,
+    /// the volume of the delay in the master mix.
+    pub level: Float,
+}
+
+impl Default for DelaySettings {
+    fn default() -> Self {
+        Self {
+            beats: 0.75,
+            feedback: 0.35,
+            level: 0.5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ReverbSettings {
+    /// how long the room rings, 0.0 to 1.0.
+    pub size: Float,
+    /// how quickly the highs fade out of the tail, 0.0 to 1.0.
+    pub damping: Float,
+    pub level: Float,
+}
+
+impl Default for ReverbSettings {
+    fn default() -> Self {
+        Self {
+            size: 0.7,
+            damping: 0.4,
+            level: 0.4,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ChorusSettings {
+    /// how fast the voices drift, in Hz.
+    pub rate: Float,
+    /// how far they drift, in milliseconds.
+    pub depth: Float,
+    pub level: Float,
+}
+
+impl Default for ChorusSettings {
+    fn default() -> Self {
+        Self {
+            rate: 0.8,
+            depth: 3.0,
+            level: 0.5,
+        }
+    }
+}
+
+/// the end of the built in synth's mix, after the effects come back.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct MasterSettings {
+    /// linear gain into the limiter.
+    pub gain: Float,
+    /// the loudest the limiter lets the output get, linear, 0.0 to 1.0.
+    pub ceiling: Float,
+}
+
+impl Default for MasterSettings {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            ceiling: 0.95,
+        }
+    }
+}
+
+/// the built in synth's send effects and master chain, saved with the song.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FxSettings {
+    pub delay: DelaySettings,
+    pub reverb: ReverbSettings,
+    pub chorus: ChorusSettings,
+    pub master: MasterSettings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SampleMode {
+    /// plays to the end of the sample whenever the note is released, for drums.
+    #[default]
+    OneShot,
+    /// fades out with the amplitude envelope's release when the note is released.
+    Gated,
+}
+
+/// one sample of a sampler, played for the notes from `low` to `high`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SampleZone {
+    /// a wav or flac file.
+    pub path: PathBuf,
+    /// the note the sample plays at its own pitch.
+    pub root: MidiNote,
+    pub low: MidiNote,
+    pub high: MidiNote,
+    pub mode: SampleMode,
+    /// start & end frames of the part of the sample that repeats while the note is held.
+    #[serde(default)]
+    pub loop_points: Option<(usize, usize)>,
+}
+
+impl SampleZone {
+    /// a zone that plays `path` at its own pitch on `note` only, one drum of a kit.
+    pub fn drum(path: impl Into<PathBuf>, note: MidiNote) -> Self {
+        Self {
+            path: path.into(),
+            root: note,
+            low: note,
+            high: note,
+            mode: SampleMode::OneShot,
+            loop_points: None,
+        }
+    }
+}
+
+/// what a built in synth track plays its notes with.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum Instrument {
+    /// the oscillators, playing the tracks wavetable.
+    #[default]
+    Oscillator,
+    /// samples mapped across the keyboard, the first zone that covers a note plays it.
+    Sampler(Vec<SampleZone>),
+    /// four sine operators modulating each other.
+    Fm(FmSettings),
+}
+
+/// how many operators an fm voice has.
+pub const N_OPERATORS: usize = 4;
+
+/// how the operators of an fm voice are wired. "a -> b" is a modulating b, the operators that
+/// aren't modulating anything are the carriers that are heard. operator 4 is the one with
+/// feedback.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FmAlgorithm {
+    /// 4 -> 3 -> 2 -> 1
+    #[default]
+    Stack,
+    /// (3 + 4) -> 2 -> 1
+    TwoIntoStack,
+    /// (3 -> 2) + 4 -> 1
+    SplitStack,
+    /// (4 -> 3) + 2 -> 1
+    BranchStack,
+    /// 2 -> 1, 4 -> 3
+    TwoStacks,
+    /// 4 -> 1, 2 & 3
+    OneToThree,
+    /// 4 -> 3, 1, 2
+    StackAndTwo,
+    /// every operator is a carrier.
+    Additive,
+}
+
+/// one sine oscillator of an fm voice.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FmOperator {
+    /// the frequency, as a multiple of the note's.
+    pub ratio: Float,
+    /// how loud a carrier is. for a modulator it's the modulation index, 1.0 bends what it
+    /// modulates by a whole cycle.
+    pub level: Float,
+    pub envelope: Adsr,
+}
+
+impl Default for FmOperator {
+    fn default() -> Self {
+        Self {
+            ratio: 1.0,
+            level: 0.0,
+            envelope: Adsr::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FmSettings {
+    pub algorithm: FmAlgorithm,
+    /// operator 1 first.
+    pub operators: [FmOperator; N_OPERATORS],
+    /// how much of operator 4 is fed back into itself, 0.0 to 1.0.
+    pub feedback: Float,
+}
+
+impl Default for FmSettings {
+    fn default() -> Self {
+        // a plain sine with a bit of a second harmonic at the start of each note.
+        let carrier = FmOperator {
+            level: 1.0,
+            ..FmOperator::default()
+        };
+        let modulator = FmOperator {
+            ratio: 2.0,
+            level: 0.3,
+            envelope: Adsr {
+                attack: 0.005,
+                decay: 0.4,
+                sustain: 0.2,
+                release: 0.2,
+            },
+        };
+
+        Self {
+            algorithm: FmAlgorithm::default(),
+            operators: [
+                carrier,
+                modulator,
+                FmOperator::default(),
+                FmOperator::default(),
+            ],
+            feedback: 0.0,
+        }
+    }
+}
+
+/// what the oscillators of a built in synth track play.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Wavetable {
-    BuiltIn(OscType),
-    FromFile(PathBuf),
+    BuiltIn(Waveform),
+    /// a wav file of `frames` single cycles of the same length, one after another. the wavetable
+    /// position sweeps through them.
+    FromFile {
+        path: PathBuf,
+        frames: usize,
+    },
+}
+
+impl Default for Wavetable {
+    fn default() -> Self {
+        Self::BuiltIn(Waveform::default())
+    }
+}
+
+/// a key on a connected midi keyboard, forwarded to the ui while playback is stopped so notes can
+/// be step entered.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyboardEvent {
+    Pressed((MidiNote, u8)),
+    Released(MidiNote),
+}
+
+/// what a note column of a track started (or stopped) playing, sent to the ui's activity monitor.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoteActivity {
+    pub track: usize,
+    pub column: usize,
+    /// the note and its velocity, `None` once the note is released.
+    pub note: Option<(MidiNote, u8)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MidiDirection {
+    In,
+    Out,
+}
+
+/// a midi message the player sent or received, shown in the ui's midi monitor.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MidiMonitorEntry {
+    /// micro-seconds since the player started.
+    pub timestamp: u64,
+    pub direction: MidiDirection,
+    pub dev: String,
+    /// `None` for system messages.
+    pub channel: Option<MidiChannel>,
+    pub bytes: Vec<u8>,
+}
+
+/// the names of the midi ports that are currently available.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MidiDevices {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+/// where the player gets its timing from.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ClockSource {
+    /// the player keeps time itself using the tempo and beat.
+    #[default]
+    Internal,
+    /// the player follows the MIDI clock received on the named midi input.
+    External(String),
+}
+
+/// reported to the UI so it can show what the player is synced to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClockStatus {
+    pub source: ClockSource,
+    /// the tempo measured from the incoming clock, `None` until enough ticks have arrived.
+    pub bpm: Option<Float>,
+    /// true between a received Start/Continue and Stop.
+    pub running: bool,
+}
+
+/// the kinds of event the back end sends to the ui. each kind is emitted under its own name so a
+/// listener only hears about the kinds it subscribes to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    Playhead,
+    NoteActivity,
+    State,
+    Delta,
+    Clock,
+    Keyboard,
+    Monitor,
+    Devices,
+    Error,
+}
+
+impl EventKind {
+    /// the event name to listen on.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Playhead => "playhead",
+            Self::NoteActivity => "note-change",
+            Self::State => "state-change",
+            Self::Delta => "state-delta",
+            Self::Clock => "clock-sync",
+            Self::Keyboard => "midi-keyboard",
+            Self::Monitor => "midi-monitor",
+            Self::Devices => "midi-devices-changed",
+            Self::Error => "backend-error",
+        }
+    }
+
+    /// lossy events are dropped, rather than waited on, when the ui falls behind. a newer one is
+    /// always on its way.
+    pub const fn lossy(self) -> bool {
+        matches!(
+            self,
+            Self::Playhead | Self::NoteActivity | Self::Clock | Self::Monitor
+        )
+    }
+}
+
+/// everything the back end tells the ui. the payload of each variant is what's emitted.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum BackendEvent {
+    /// the row that was just played, or where the playhead was moved to.
+    Playhead(usize),
+    NoteActivity(NoteActivity),
+    /// a copy of the rows the ui is showing.
+    State(TrackerState),
+    /// the cells changed by an edit.
+    Delta(StateDelta),
+    Clock(ClockStatus),
+    /// keys played on a midi keyboard while not playing.
+    Keyboard(KeyboardEvent),
+    Monitor(MidiMonitorEntry),
+    Devices(MidiDevices),
+    /// something went wrong that the user should hear about.
+    Error(String),
+}
+
+impl BackendEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::Playhead(_) => EventKind::Playhead,
+            Self::NoteActivity(_) => EventKind::NoteActivity,
+            Self::State(_) => EventKind::State,
+            Self::Delta(_) => EventKind::Delta,
+            Self::Clock(_) => EventKind::Clock,
+            Self::Keyboard(_) => EventKind::Keyboard,
+            Self::Monitor(_) => EventKind::Monitor,
+            Self::Devices(_) => EventKind::Devices,
+            Self::Error(_) => EventKind::Error,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -293,15 +1163,95 @@ pub enum PlayerCmd {
     PausePlayback,
     ResumePlayback,
     StopPlayback,
+    /// plays from the start of the song.
+    RestartPlayback,
+    /// plays from a row.
+    PlayFrom(usize),
     // SetPlayingChannels(Channel),
     // SetTarget(MidiTarget),
     SetCursor(usize),
     SetTempo(u64),
     SetBeat(u64),
-    // SetWavetable((ChannelIndex, Wavetable)),
+    SetWavetable((ChannelIndex, Wavetable)),
+    /// where the oscillators of a track read from a multi-frame wavetable, 0.0 to 1.0.
+    SetWavetablePos(ChannelIndex, Float),
+    /// picks what a track plays its notes with on the built in synth.
+    SetInstrument(ChannelIndex, Instrument),
+    /// how much of a track goes to each effect.
+    SetSends(ChannelIndex, Sends),
+    SetFx(FxSettings),
+    /// the song was replaced by a saved project, the synth is set up the way it was saved.
+    ProjectLoaded,
+    /// what note velocity does to a track.
+    SetVelocity(ChannelIndex, VelocitySettings),
+    /// polyphony, mono/poly & voice stealing of a track.
+    SetVoices(ChannelIndex, VoiceSettings),
+    /// portamento between the notes of a track.
+    SetGlide(ChannelIndex, GlideSettings),
+    /// the lfos & modulation routing of a track.
+    SetModulation(ChannelIndex, ModMatrix),
+    /// sets every part of a track's sound at once.
+    LoadPreset(ChannelIndex, InstrumentPreset),
     SetRecHead(usize, usize),
+    SetClockSource(ClockSource),
+    /// connects to a midi input so it can be recorded from.
+    ConnectMidiIn(String),
+    /// arms/disarms recording of midi input at the record head.
+    SetRecording(bool),
+    /// sent by the device monitor when midi devices are plugged in or unplugged.
+    MidiDevicesChanged(MidiDevices),
+    /// mutes/unmutes a track.
+    SetMute(ChannelIndex, bool),
+    /// sends all notes off & all sound off on every channel of every midi output.
+    Panic,
+    /// stops every note still sounding on a track, before it's moved to a different output.
+    TrackNotesOff(ChannelIndex),
+    /// sends raw (already validated) midi bytes to the named midi output.
+    SendMidi(String, Vec<u8>),
+    /// pans a track on the built in synth, -1.0 (left) to 1.0 (right).
+    SetPan(ChannelIndex, Float),
+    /// sets the amplitude envelope of a track on the built in synth.
+    SetEnvelope(ChannelIndex, Adsr),
+    /// sets the filter of a track on the built in synth.
+    SetFilter(ChannelIndex, FilterSettings),
 }
 
+/// the midi cc that moves the filter cutoff of a track on the built in synth, its brightness.
+pub const CUTOFF_CC: u8 = 74;
+/// the midi cc that moves the filter resonance of a track on the built in synth, its harmonic
+/// content.
+pub const RESONANCE_CC: u8 = 71;
+
+/// the effect command that pans a track on the built in synth.
+pub const PAN_CMD: Cmd = 'P';
+/// the effect command that sets the filter cutoff of a track on the built in synth.
+pub const CUTOFF_CMD: Cmd = 'F';
+/// the effect command that sets the filter resonance of a track on the built in synth.
+pub const RESONANCE_CMD: Cmd = 'Q';
+/// the effect command that sets how long notes of a track on the built in synth slide for, 00 turns
+/// glide off.
+pub const GLIDE_CMD: Cmd = 'G';
+
 pub fn get_cmd_arg_val(arg: CmdArg) -> usize {
     ((arg as Float / CmdArg::MAX as Float) * 100.0).round() as usize
 }
+
+/// a commands argument as a pan, 0 is hard left, 50 the center and 100 hard right.
+pub fn get_cmd_arg_pan(arg: CmdArg) -> Float {
+    get_cmd_arg_val(arg) as Float / 50.0 - 1.0
+}
+
+/// a filter cutoff swept exponentially, from 20 Hz at 0.0 to 20 kHz at 1.0.
+pub fn sweep_cutoff(amount: Float) -> Float {
+    20.0 * (1000.0 as Float).powf(amount.clamp(0.0, 1.0))
+}
+
+/// a commands argument as a glide time, 0 to 1 second.
+pub fn get_cmd_arg_glide(arg: CmdArg) -> Float {
+    get_cmd_arg_val(arg) as Float / 100.0
+}
+
+/// a commands argument as a filter cutoff, see `sweep_cutoff`.
+pub fn get_cmd_arg_cutoff(arg: CmdArg) -> Float {
+    sweep_cutoff(get_cmd_arg_val(arg) as Float / 100.0)
+}
@@ -0,0 +1,134 @@
+use super::{Cmd, CmdArg, MidiNote, MidiNoteCmd, TrackerState};
+use std::collections::HashSet;
+
+/// how long a row lasts in nanoseconds, 1/`beat` of a beat at `tempo` bpm.
+pub fn row_nanos(tempo: u64, beat: u64) -> u64 {
+    60_000_000_000 / tempo.max(1) / beat.max(1)
+}
+
+/// something a track does when a row is played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowEvent {
+    /// a note column played, stopped or held a note.
+    Note {
+        track: usize,
+        column: usize,
+        note: MidiNoteCmd,
+    },
+    /// a midi control change from the track's cc lane.
+    Cc {
+        track: usize,
+        control: u8,
+        value: u8,
+    },
+    /// an effect command.
+    Cmd {
+        track: usize,
+        cmd: (Cmd, Option<CmdArg>),
+    },
+}
+
+/// steps through a song a row at a time. the player and the offline renderer both play songs
+/// through it, so a bounced song sounds the way it plays.
+#[derive(Clone, Debug, Default)]
+pub struct Timeline {
+    /// the row played last.
+    playhead: usize,
+    /// notes the song started that haven't been stopped yet, as (track, column, note).
+    sounding: HashSet<(usize, usize, MidiNote)>,
+}
+
+impl Timeline {
+    pub fn playhead(&self) -> usize {
+        self.playhead
+    }
+
+    /// moves to `row` without playing anything. whatever was sounding is forgotten, it should
+    /// have been silenced already.
+    pub fn seek(&mut self, row: usize) {
+        self.playhead = row;
+        self.sounding.clear();
+    }
+
+    /// what playing `row` does, each track's control change, notes then commands. the notes of
+    /// tracks that are `muted` are left out but their control changes & commands still apply, so
+    /// they're in the right state when they're unmuted. when `row` comes before the last row
+    /// played the song has looped, and anything still sounding is stopped first.
+    pub fn step(
+        &mut self,
+        song: &TrackerState,
+        row: usize,
+        muted: impl Fn(usize) -> bool,
+    ) -> Vec<RowEvent> {
+        let mut events = if row < self.playhead {
+            self.release()
+        } else {
+            Vec::new()
+        };
+
+        self.playhead = row;
+
+        for (track, sequence) in song.sequences.iter().enumerate() {
+            let Some(row_dat) = row
+                .checked_rem(sequence.data.len())
+                .map(|row| sequence.data[row])
+            else {
+                continue;
+            };
+
+            if let Some((control, value)) = row_dat.cc {
+                events.push(RowEvent::Cc {
+                    track,
+                    control,
+                    value,
+                });
+            }
+
+            if !muted(track) {
+                for (column, note) in row_dat.notes.into_iter().enumerate() {
+                    let Some(note) = note else {
+                        continue;
+                    };
+
+                    match note {
+                        MidiNoteCmd::PlayNote((midi_note, _)) => {
+                            self.sounding.insert((track, column, midi_note));
+                        }
+                        MidiNoteCmd::StopNote(midi_note) => self
+                            .sounding
+                            .retain(|(on, _, sounding)| (*on, *sounding) != (track, midi_note)),
+                        MidiNoteCmd::HoldNote => {}
+                    }
+
+                    events.push(RowEvent::Note {
+                        track,
+                        column,
+                        note,
+                    });
+                }
+            }
+
+            events.extend(
+                row_dat
+                    .cmds
+                    .into_iter()
+                    .flatten()
+                    .map(|cmd| RowEvent::Cmd { track, cmd }),
+            );
+        }
+
+        events
+    }
+
+    /// stops every note the song left sounding.
+    pub fn release(&mut self) -> Vec<RowEvent> {
+        self.sounding
+            .drain()
+            .map(|(track, column, note)| RowEvent::Note {
+                track,
+                column,
+                note: MidiNoteCmd::StopNote(note),
+            })
+            .collect()
+    }
+}
@@ -3,10 +3,8 @@ use anyhow::Result;
 use bevy::{app::AppExit, prelude::*, window::PresentMode};
 use crossbeam::channel::Sender;
 use player::Player;
-use rodio::OutputStream;
 use state::{AppState, MainScreenMode};
-use std::sync::{Arc, Mutex};
-use synth_lib::audio::{AudioOutputSync, TrackerSynth};
+use synth_lib::{audio::SynthHandle, init_synth};
 use tracker_lib::{PlayerCmd, TrackerState};
 
 mod main_menu;
@@ -15,7 +13,7 @@ mod setup;
 pub mod state;
 
 #[derive(Resource)]
-pub struct Synth(Arc<Mutex<TrackerSynth>>);
+pub struct Synth(SynthHandle);
 
 #[derive(Resource)]
 pub struct PlayerSynth(Player);
@@ -24,12 +22,7 @@ pub struct PlayerSynth(Player);
 pub struct PlayerIpc(Sender<PlayerCmd>);
 
 fn main() -> Result<()> {
-    let synth = Arc::new(Mutex::new(TrackerSynth::default()));
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    let audio = AudioOutputSync {
-        synth: synth.clone(),
-        _stream,
-    };
+    let (synth, stream_handle, audio) = init_synth(2)?;
 
     info!("starting audio stream");
     stream_handle.play_raw(audio).unwrap();
@@ -3,10 +3,9 @@ use bevy::prelude::*;
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use std::{
     ops::DerefMut,
-    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
-use synth_lib::{audio::TrackerSynth, Note};
+use synth_lib::audio::SynthHandle;
 use tracker_lib::Float;
 use tracker_lib::{
     Channel, Cmd, CmdArg, MidiNoteCmd, MidiTarget, PlaybackState, PlayerCmd, TrackerState,
@@ -34,7 +33,7 @@ pub struct Player {
     /// usedd to receive control commands from other threads.
     ipc: Receiver<PlayerCmd>,
     /// the synth that is used when `self.target` is set to `MidiTarget::BuiltinSynth`.
-    synth: Arc<Mutex<TrackerSynth>>,
+    synth: SynthHandle,
     /// time till next event in nano_seconds
     // ttne: Mutex<usize>,
     /// the instant that the last beat was processed
@@ -46,13 +45,13 @@ pub struct Player {
 impl Player {
     pub fn new(
         // song: Arc<Mutex<TrackerState>>,
-        synth: Arc<Mutex<TrackerSynth>>,
+        synth: SynthHandle,
     ) -> (Self, Sender<PlayerCmd>) {
         let (tx, rx) = unbounded();
 
         (
             Player {
-                state: PlaybackState::NotPlaying,
+                state: PlaybackState::default(),
                 target: MidiTarget::BuiltinSynth,
                 channels: Channel::AllChannels,
                 ipc: rx,
@@ -68,19 +67,19 @@ impl Player {
 
     fn send_note(&mut self, note: MidiNoteCmd, channel: usize) {
         // let note = Note::from(note);
-        let (note, play) = match note {
-            MidiNoteCmd::PlayNote(note) => (Note::from(note), true),
-            MidiNoteCmd::StopNote(note) => (Note::from(note), false),
+        let (note, velocity, play) = match note {
+            MidiNoteCmd::PlayNote((note, velocity)) => (note, velocity, true),
+            MidiNoteCmd::StopNote(note) => (note, 0, false),
         };
 
         match self.target {
             MidiTarget::BuiltinSynth => {
                 if play {
-                    if let Err(e) = self.synth.lock().unwrap().play(note, channel) {
+                    if let Err(e) = self.synth.play(note, velocity, channel) {
                         error!("the built in synth failed to play \"{note}\" on channel \"{channel}\". failed with error {e}.")
                     }
                 } else {
-                    if let Err(e) = self.synth.lock().unwrap().stop(note, channel) {
+                    if let Err(e) = self.synth.stop(note, channel) {
                         error!("the built in synth failed to play \"{note}\" on channel \"{channel}\". failed with error {e}.")
                     }
                 }
@@ -94,7 +93,7 @@ impl Player {
         //
         // match self.target {
         //     MidiTarget::BuiltinSynth => {
-        //         self.synth.lock().unwrap().play(note, channel);
+        //         self.synth.play(note, channel);
         //     }
         //     _ => error!("not implemented yet"),
         // }
@@ -108,7 +107,7 @@ impl Player {
         if let Ok(cmd_msg) = self.ipc.try_recv() {
             match cmd_msg {
                 PlayerCmd::VolumeSet((vol, channel)) => {
-                    if let Err(e) = self.synth.lock().unwrap().set_volume(vol, channel) {
+                    if let Err(e) = self.synth.set_volume(vol, channel) {
                         error!("{e}");
                     }
                 }
@@ -119,28 +118,24 @@ impl Player {
                         self.state = PlaybackState::Paused(line_num)
                     }
                     PlaybackState::Paused(_) => error!("playback is already paused."),
-                    PlaybackState::NotPlaying => error!("can't pause, not playing."),
+                    PlaybackState::Stopped(_) => error!("can't pause, not playing."),
                 },
                 PlayerCmd::ResumePlayback => match self.state {
                     PlaybackState::Playing(_) => error!("can't play while already playing."),
                     PlaybackState::Paused(line_num) => {
                         self.state = PlaybackState::Playing(line_num)
                     }
-                    PlaybackState::NotPlaying => self.state = PlaybackState::Playing(0),
+                    PlaybackState::Stopped(line_num) => self.state = PlaybackState::Playing(line_num),
                 },
                 PlayerCmd::StopPlayback => {
-                    if let PlaybackState::NotPlaying = self.state {
-                        error!("can't stop playing while already not playing");
-                    } else {
-                        self.state = PlaybackState::NotPlaying;
-                    }
+                    self.state = PlaybackState::Stopped(0);
                 }
+                PlayerCmd::RestartPlayback => self.state = PlaybackState::Playing(0),
+                PlayerCmd::PlayFrom(loc) => self.state = PlaybackState::Playing(loc),
                 PlayerCmd::SetCursor(loc) => match self.state {
                     PlaybackState::Playing(_) => self.state = PlaybackState::Playing(loc),
                     PlaybackState::Paused(_) => self.state = PlaybackState::Paused(loc),
-                    PlaybackState::NotPlaying => {
-                        error!("can't set cursor location when there is no cursor location to set.")
-                    }
+                    PlaybackState::Stopped(_) => self.state = PlaybackState::Stopped(loc),
                 },
             }
         }
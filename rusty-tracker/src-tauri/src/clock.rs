@@ -0,0 +1,117 @@
+use tracker_lib::Float;
+
+/// midi clock runs at 24 pulses per quarter note.
+pub const TICKS_PER_QUARTER: u64 = 24;
+/// a song position pointer counts midi beats (16th notes), each is 6 clock ticks.
+const TICKS_PER_MIDI_BEAT: u64 = 6;
+/// how much each new tick interval moves the smoothed interval.
+const SMOOTHING: Float = 0.08;
+
+/// follows the midi clock of an external sequencer.
+pub struct ExternalClock {
+    /// the name of the midi input the clock is read from.
+    pub dev: String,
+    /// true between a Start/Continue and a Stop message.
+    pub running: bool,
+    /// timestamp of the last received tick in micro-seconds.
+    last_tick: Option<u64>,
+    /// the smoothed time between ticks in micro-seconds.
+    tick_interval: Option<Float>,
+    /// the song position, in ticks, that the next received tick lands on.
+    next_tick: u64,
+    /// the last row that was reached, so a row is only played once.
+    last_row: Option<usize>,
+    /// counts ticks so the ui can be updated once per quarter note.
+    ticks_since_report: u64,
+}
+
+impl ExternalClock {
+    pub fn new(dev: String) -> Self {
+        Self {
+            dev,
+            running: false,
+            last_tick: None,
+            tick_interval: None,
+            next_tick: 0,
+            last_row: None,
+            ticks_since_report: 0,
+        }
+    }
+
+    /// measures the time since the last tick. returns the row reached by this tick, if the clock
+    /// is running and the tick moves playback onto a new row.
+    pub fn tick(&mut self, timestamp: u64, beat: u64) -> Option<usize> {
+        if let Some(last) = self.last_tick {
+            let interval = timestamp.saturating_sub(last) as Float;
+
+            self.tick_interval = match self.tick_interval {
+                // a large jump means the master changed tempo abruptly (or stalled), so start over
+                // rather than slowly drifting towards it.
+                Some(smoothed) if interval < smoothed * 4.0 && interval > smoothed / 4.0 => {
+                    Some(smoothed + (interval - smoothed) * SMOOTHING)
+                }
+                _ => Some(interval),
+            };
+        }
+
+        self.last_tick = Some(timestamp);
+        self.ticks_since_report += 1;
+
+        if !self.running {
+            return None;
+        }
+
+        let row = (self.next_tick * beat / TICKS_PER_QUARTER) as usize;
+        self.next_tick += 1;
+
+        if self.last_row != Some(row) {
+            self.last_row = Some(row);
+
+            Some(row)
+        } else {
+            None
+        }
+    }
+
+    /// the first tick after a Start lands on the beginning of the song.
+    pub fn start(&mut self) {
+        self.next_tick = 0;
+        self.last_row = None;
+        self.running = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.running = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    /// moves to a song position pointer, given in midi beats. returns the row it lands on.
+    pub fn set_position(&mut self, midi_beats: u16, beat: u64) -> usize {
+        self.next_tick = midi_beats as u64 * TICKS_PER_MIDI_BEAT;
+        self.last_row = None;
+
+        (self.next_tick * beat / TICKS_PER_QUARTER) as usize
+    }
+
+    /// the tempo of the master in beats per minute.
+    pub fn bpm(&self) -> Option<Float> {
+        self.tick_interval
+            .filter(|interval| *interval > 0.0)
+            .map(|interval| 60_000_000.0 / (interval * TICKS_PER_QUARTER as Float))
+    }
+
+    /// returns true once every quarter note worth of ticks, used to limit how often the ui is
+    /// told about the measured tempo.
+    pub fn should_report(&mut self) -> bool {
+        if self.ticks_since_report >= TICKS_PER_QUARTER {
+            self.ticks_since_report = 0;
+
+            true
+        } else {
+            false
+        }
+    }
+}
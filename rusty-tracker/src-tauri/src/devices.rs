@@ -0,0 +1,74 @@
+use crate::events::EventBus;
+use crossbeam::channel::Sender;
+use midir::{MidiInput, MidiOutput};
+use std::{thread, time::Duration};
+use tracing::*;
+use tracker_lib::{BackendEvent, MidiDevices, PlayerCmd, DEFAULT_MIDI_DEV_NAME};
+
+/// how often the available midi ports are checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+fn current_devices(midi_in: &MidiInput, midi_out: &MidiOutput) -> MidiDevices {
+    MidiDevices {
+        inputs: midi_in
+            .ports()
+            .iter()
+            .filter_map(|port| midi_in.port_name(port).ok())
+            .collect(),
+        outputs: [DEFAULT_MIDI_DEV_NAME.to_string()]
+            .into_iter()
+            .chain(
+                midi_out
+                    .ports()
+                    .iter()
+                    .filter_map(|port| midi_out.port_name(port).ok()),
+            )
+            .collect(),
+    }
+}
+
+/// watches for midi devices being plugged in or unplugged. when the available ports change the
+/// player is told, so it can drop stale connections and reconnect returning devices, and so is
+/// the ui.
+pub fn monitor_devices(events: EventBus, player: Sender<PlayerCmd>) {
+    thread::spawn(move || {
+        let (midi_in, midi_out) = match (
+            MidiInput::new("rusty-tracker device monitor"),
+            MidiOutput::new("rusty-tracker device monitor"),
+        ) {
+            (Ok(midi_in), Ok(midi_out)) => (midi_in, midi_out),
+            (Err(e), _) => {
+                error!("could not start the midi device monitor. {e}");
+                events.error(format!("could not start the midi device monitor. {e}"));
+                return;
+            }
+            (_, Err(e)) => {
+                error!("could not start the midi device monitor. {e}");
+                events.error(format!("could not start the midi device monitor. {e}"));
+                return;
+            }
+        };
+
+        let mut devices = current_devices(&midi_in, &midi_out);
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let now = current_devices(&midi_in, &midi_out);
+
+            if now == devices {
+                continue;
+            }
+
+            info!("midi devices changed. inputs: {:?}, outputs: {:?}", now.inputs, now.outputs);
+
+            if let Err(e) = player.send(PlayerCmd::MidiDevicesChanged(now.clone())) {
+                error!("could not tell the player about the midi device change. {e}");
+            }
+
+            events.publish(BackendEvent::Devices(now.clone()));
+
+            devices = now;
+        }
+    });
+}
@@ -0,0 +1,147 @@
+use crate::{HashMap, WEB_VIEW_WINDOW};
+use crossbeam::channel::{
+    bounded, Receiver, RecvTimeoutError, SendTimeoutError, Sender, TrySendError,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::*;
+use tracker_lib::{BackendEvent, NoteActivity};
+
+/// how many events can be waiting to go to the ui before the bus pushes back.
+const EVENT_QUEUE_LEN: usize = 1024;
+/// how long a publisher waits on a full queue before giving up on an event that can't be dropped.
+const SEND_TIMEOUT: Duration = Duration::from_millis(10);
+/// the fastest note activity is sent to the ui, about 30 times a second.
+const NOTE_ACTIVITY_INTERVAL: Duration = Duration::from_millis(33);
+
+/// the one way events get from the back end to the ui. cheap to clone, every part of the back end
+/// that has something to say holds one.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: Sender<BackendEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> (Self, Receiver<BackendEvent>) {
+        let (tx, rx) = bounded(EVENT_QUEUE_LEN);
+
+        (Self { tx }, rx)
+    }
+
+    /// queues `event` for the ui. when the ui falls behind lossy events are dropped, the rest wait
+    /// a little for room so the player is never stalled for long.
+    pub fn publish(&self, event: BackendEvent) {
+        let kind = event.kind();
+
+        if kind.lossy() {
+            match self.tx.try_send(event) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => trace!("event queue full, dropped a {kind:?} event"),
+                Err(TrySendError::Disconnected(_)) => {
+                    error!("the event forwarder stopped, dropped a {kind:?} event")
+                }
+            }
+        } else if let Err(e) = self.tx.send_timeout(event, SEND_TIMEOUT) {
+            match e {
+                SendTimeoutError::Timeout(_) => {
+                    error!("the ui is not keeping up, dropped a {kind:?} event")
+                }
+                SendTimeoutError::Disconnected(_) => {
+                    error!("the event forwarder stopped, dropped a {kind:?} event")
+                }
+            }
+        }
+    }
+
+    /// tells the ui about an error it should show the user.
+    pub fn error(&self, mesg: impl ToString) {
+        self.publish(BackendEvent::Error(mesg.to_string()));
+    }
+}
+
+fn emit(app: &AppHandle, event: BackendEvent) {
+    let Some(window) = app.get_webview_window(WEB_VIEW_WINDOW) else {
+        return;
+    };
+
+    let name = event.kind().name();
+
+    let res = match event {
+        BackendEvent::Playhead(row) => window.emit(name, row),
+        BackendEvent::NoteActivity(activity) => window.emit(name, activity),
+        BackendEvent::State(state) => window.emit(name, state),
+        BackendEvent::Delta(delta) => window.emit(name, delta),
+        BackendEvent::Clock(status) => window.emit(name, status),
+        BackendEvent::Keyboard(key) => window.emit(name, key),
+        BackendEvent::Monitor(entry) => window.emit(name, entry),
+        BackendEvent::Devices(devices) => window.emit(name, devices),
+        BackendEvent::Error(mesg) => window.emit(name, mesg),
+    };
+
+    if let Err(e) = res {
+        error!("emitting {name} to the ui failed. {e}");
+    }
+}
+
+/// emits everything published on the bus to the ui. events that pile up are coalesced, only the
+/// latest playhead, song state, clock status and activity of each note column are sent.
+pub fn forward_events(app: AppHandle, rx: Receiver<BackendEvent>) {
+    thread::spawn(move || {
+        let mut notes: HashMap<(usize, usize), NoteActivity> = HashMap::default();
+        let mut last_notes = Instant::now();
+
+        loop {
+            let first = match rx.recv_timeout(NOTE_ACTIVITY_INTERVAL) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => {
+                    warn!("event bus closed, no more events will reach the ui.");
+                    return;
+                }
+            };
+
+            let mut playhead = None;
+            let mut state = None;
+            let mut clock = None;
+            let mut rest = Vec::new();
+
+            for event in first.into_iter().chain(rx.try_iter().take(EVENT_QUEUE_LEN)) {
+                match event {
+                    BackendEvent::Playhead(row) => playhead = Some(row),
+                    BackendEvent::NoteActivity(activity) => {
+                        notes.insert((activity.track, activity.column), activity);
+                    }
+                    BackendEvent::State(song) => state = Some(song),
+                    BackendEvent::Clock(status) => clock = Some(status),
+                    event => rest.push(event),
+                }
+            }
+
+            // the copy of the song goes first, deltas it already has are skipped by the ui, those
+            // it doesn't are applied on top.
+            if let Some(song) = state {
+                emit(&app, BackendEvent::State(song));
+            }
+
+            rest.into_iter().for_each(|event| emit(&app, event));
+
+            if let Some(row) = playhead {
+                emit(&app, BackendEvent::Playhead(row));
+            }
+
+            if let Some(status) = clock {
+                emit(&app, BackendEvent::Clock(status));
+            }
+
+            if !notes.is_empty() && last_notes.elapsed() >= NOTE_ACTIVITY_INTERVAL {
+                notes
+                    .drain()
+                    .for_each(|(_, activity)| emit(&app, BackendEvent::NoteActivity(activity)));
+                last_notes = Instant::now();
+            }
+        }
+    });
+}
@@ -1,41 +1,70 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use anyhow::bail;
+use clock::ExternalClock;
+use events::EventBus;
 use crossbeam::channel::{unbounded, Receiver, Sender};
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use midi_control::{Channel, KeyEvent, MidiMessage};
-use midir::{os::unix::VirtualOutput, MidiOutput, MidiOutputConnection};
+use midi_in::{connect_input, list_input_ports, MidiInEvent, MidiInMsg};
+use midi_out::{
+    connect_output, control_change, list_output_ports, message_channel, new_midi_dev,
+    validate_midi, ALL_NOTES_OFF, ALL_SOUND_OFF,
+};
+use midir::{MidiInputConnection, MidiOutputConnection};
 use std::{
-    future::Future, pin::Pin, sync::{Arc, Mutex as StdMutex}, task::Poll, time::{Duration, Instant}
+    fs, future::Future, pin::Pin, sync::{Arc, Mutex as StdMutex}, task::Poll, thread, time::{Duration, Instant}
+};
+use synth_lib::{
+    audio::{SynthCmd, SynthHandle},
+    init_synth,
+    preset::{load_preset as read_preset, preset_cmds, save_preset as write_preset},
+    sampler::Kit,
+    wavetable::Table,
 };
-// use synth_lib::{audio::TrackerSynth, init_synth, Note};
 use tauri::{
-    async_runtime::{spawn, JoinHandle, Mutex},
-    Emitter, Manager, State, Window,
+    async_runtime::{spawn, Mutex},
+    State,
 };
 // use tauri_sys::window::current_window;
 use tracing::*;
 use tracker_lib::{
-    ChannelIndex, Cmd, CmdArg, MidiNote, MidiNoteCmd, PlaybackCmd, PlaybackState, PlayerCmd,
-    TrackerState, DEFAULT_MIDI_DEV_NAME,
+    timeline::{row_nanos, RowEvent, Timeline},
+    Adsr, BackendEvent, ChannelIndex, ClockSource, ClockStatus, Cmd, CmdArg, FilterSettings, Float,
+    FxSettings, GlideSettings, Instrument, InstrumentPreset, KeyboardEvent, MidiChannel,
+    MidiDevices, MidiDirection, MidiMonitorEntry, MidiNote, MidiNoteCmd, MidiTarget, ModMatrix,
+    NoteActivity, PlaybackCmd, PlaybackState, PlayerCmd, RowData, Sends, StateDelta, TrackerState,
+    VelocitySettings, VoiceSettings, Wavetable, DEFAULT_MIDI_DEV_NAME,
 };
 
+mod clock;
+mod devices;
+mod events;
+mod midi_in;
+mod midi_out;
+
 pub type HashMap<K, V> = FxHashMap<K, V>;
+pub type HashSet<T> = FxHashSet<T>;
 
 pub const MAX_COL_LEN: usize = 0xFFFF;
 // pub const WEB_VIEW_WINDOW: &str = "Midi-Tracker";
 pub const WEB_VIEW_WINDOW: &str = "main";
 const NANO_MIN: u64 = 60_000_000_000;
 
-struct IO {
-    line_out: JoinHandle<()>,
-    note_out: JoinHandle<()>,
+/// the rows the ui is currently showing, so edits made by the player can be sent to it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ViewWindow {
+    start_row: usize,
+    n_rows: usize,
 }
 
 // #[derive(Serialize, Deserialize, Clone)]
 pub struct Player {
     /// describes the state of playback e.g. playing, paused, etc.
     state: PlaybackState,
+    /// a copy of `state` that the `get_playback_state` command reads.
+    shared_state: Arc<StdMutex<PlaybackState>>,
+    /// what was last written to `shared_state`, so it's only locked when the state changes.
+    shared: PlaybackState,
     // /// describes where the midi data should be sent.
     // target: MidiTarget,
     // /// used to describe which channels should be played. all not here are ignored during playback.
@@ -44,12 +73,22 @@ pub struct Player {
     ipc: Receiver<PlayerCmd>,
     /// the state of the song the user has written.
     song: Arc<StdMutex<TrackerState>>,
-    // /// the synth that is used when `self.target` is set to `MidiTarget::BuiltinSynth`.
-    // synth: Arc<Mutex<TrackerSynth>>,
+    /// the synth that plays tracks targeting `MidiTarget::BuiltinSynth`, `None` when there's no
+    /// audio output.
+    synth: Option<SynthHandle>,
+    /// notes sounding on the built in synth, by track.
+    synth_notes: HashSet<(usize, MidiNote)>,
     // /// time till next event in nano_seconds
     // ttne: Mutex<usize>,
-    /// virtual mdii output devices
+    /// midi output devices, the virtual one is made at start up the rest are connected to the
+    /// first time a track uses them.
     midi_outs: HashMap<String, MidiOutputConnection>,
+    /// devices that couldn't be connected to, so the error is only logged once.
+    unknown_devs: HashSet<String>,
+    /// notes that have been sent a note on but not yet a note off, by device and channel.
+    active_notes: HashMap<(String, MidiChannel), HashSet<MidiNote>>,
+    /// tracks that are skipped during playback.
+    muted: HashSet<usize>,
     /// the instant that the last beat was processed
     last_event: Instant,
     /// the amount of time between beats
@@ -59,26 +98,38 @@ pub struct Player {
     /// which beat describes the time between rows
     beat: u64,
     // window: Option<Window>,
-    line_out: Sender<usize>,
-    notes_out: Sender<(usize, Option<MidiNote>)>,
+    /// playhead, note activity, song edits, etc. are published here for the ui.
+    events: EventBus,
     rec_head: (usize, usize),
+    /// connected midi inputs, keyed by port name.
+    midi_ins: HashMap<String, MidiInputConnection<()>>,
+    /// every midi input the user asked for, so they can be reconnected if they're unplugged.
+    wanted_ins: HashSet<String>,
+    /// handed to the midi input callbacks so they can forward received messages to the player.
+    midi_in_tx: Sender<MidiInEvent>,
+    midi_in_rx: Receiver<MidiInEvent>,
+    /// set when playback follows the midi clock of an external sequencer.
+    clock: Option<ExternalClock>,
+    /// steps through the song, it knows the row that was most recently played.
+    timeline: Timeline,
+    /// when true, notes received on the midi inputs are written into the song during playback.
+    recording: bool,
+    /// notes that are being recorded but have not been released yet. maps the note to the row and
+    /// note column it was started at.
+    rec_notes: HashMap<MidiNote, (usize, usize)>,
+    /// when the player was made, midi monitor timestamps are relative to this.
+    started: Instant,
 }
 
 impl Player {
     pub fn new(
         song: Arc<StdMutex<TrackerState>>,
-        // synth: Arc<Mutex<TrackerSynth>>,
-    ) -> (
-        Self,
-        (
-            Sender<PlayerCmd>,
-            Receiver<usize>,
-            Receiver<(usize, Option<MidiNote>)>,
-        ),
-    ) {
+        shared_state: Arc<StdMutex<PlaybackState>>,
+        events: EventBus,
+        synth: Option<SynthHandle>,
+    ) -> (Self, Sender<PlayerCmd>) {
         let (tx, rx) = unbounded();
-        let (line_tx, line_rx) = unbounded();
-        let (note_tx, note_rx) = unbounded();
+        let (midi_in_tx, midi_in_rx) = unbounded();
         let tempo = 110;
         let beat = 8;
         let mut midi_outs = HashMap::default();
@@ -96,23 +147,37 @@ impl Player {
 
         (
             Player {
-                state: PlaybackState::NotPlaying,
+                state: PlaybackState::default(),
+                shared_state,
+                shared: PlaybackState::default(),
                 // target: MidiTarget::BuiltinSynth,
                 // channels: Channel::AllChannels,
                 ipc: rx,
                 song,
                 // ttne: Mutex::new(0),
                 midi_outs,
+                unknown_devs: HashSet::default(),
+                active_notes: HashMap::default(),
+                muted: HashSet::default(),
                 last_event: Instant::now(),
-                beat_time: Duration::from_nanos(NANO_MIN / tempo / beat),
-                // synth,
+                beat_time: Duration::from_nanos(row_nanos(tempo, beat)),
+                synth,
+                synth_notes: HashSet::default(),
                 tempo,
                 beat,
-                line_out: line_tx,
-                notes_out: note_tx,
+                events,
                 rec_head: (0, 0),
+                midi_ins: HashMap::default(),
+                wanted_ins: HashSet::default(),
+                midi_in_tx,
+                midi_in_rx,
+                clock: None,
+                timeline: Timeline::default(),
+                recording: false,
+                rec_notes: HashMap::default(),
+                started: Instant::now(),
             },
-            (tx, line_rx, note_rx),
+            tx,
         )
     }
 
@@ -129,7 +194,7 @@ impl Player {
         };
 
         //  sends note on/off messages to the selected midi device and channel
-        if let Some(out) = self.midi_outs.get_mut(&dev_name) {
+        let sent = {
             let channels = [
                 Channel::Ch1,
                 Channel::Ch2,
@@ -167,443 +232,1702 @@ impl Player {
                 )
             };
 
-            if let Err(e) = out.send(&Vec::from(cmd)) {
-                error!("tried to sending midi output message, resulted in error {e}.");
+            self.send_raw(&dev_name, &Vec::from(cmd))
+        };
+
+        let active = self.active_notes.entry((dev_name, channel)).or_default();
+
+        if play && sent {
+            active.insert(note);
+        } else {
+            active.remove(&note);
+        }
+    }
+
+    /// plays/stops a note on the built in synth voice of `track`.
+    fn synth_note(&mut self, note: MidiNoteCmd, track: usize) {
+        let Some(ref synth) = self.synth else {
+            return;
+        };
+
+        match note {
+            MidiNoteCmd::PlayNote((midi_note, vel)) => {
+                if let Err(e) = synth.play(midi_note, vel, track) {
+                    error!("the built in synth failed to play {midi_note} on track {}. {e}", track + 1);
+                } else {
+                    self.synth_notes.insert((track, midi_note));
+                }
+            }
+            MidiNoteCmd::StopNote(midi_note) => {
+                if self.synth_notes.remove(&(track, midi_note)) {
+                    if let Err(e) = synth.stop(midi_note, track) {
+                        error!("the built in synth failed to stop {midi_note} on track {}. {e}", track + 1);
+                    }
+                }
             }
+            MidiNoteCmd::HoldNote => {}
         }
-        // match self.target {
-        //     // MidiTarget::BuiltinSynth => {
-        //     //     if play {
-        //     //         if let Err(e) = self.synth.lock().unwrap().play(note, channel) {
-        //     //             error!("the built in synth failed to play \"{note}\" on channel \"{channel}\". failed with error {e}.")
-        //     //         }
-        //     //     } else {
-        //     //         if let Err(e) = self.synth.lock().unwrap().stop(note, channel) {
-        //     //             error!("the built in synth failed to play \"{note}\" on channel \"{channel}\". failed with error {e}.")
-        //     //         }
-        //     //     }
-        //     // }
-        //     _ => error!("not implemented yet"),
-        // }
     }
 
-    fn send_cmd(&mut self, _command: (Cmd, Option<CmdArg>), _channel: usize) {
-        // TODO: implement this
+    /// stops the built in synths notes, on `track` or on every track.
+    fn synth_notes_off(&mut self, track: Option<usize>) {
+        let sounding: Vec<(usize, MidiNote)> = self
+            .synth_notes
+            .iter()
+            .filter(|(note_track, _)| track.is_none_or(|track| track == *note_track))
+            .copied()
+            .collect();
+
+        sounding
+            .into_iter()
+            .for_each(|(track, note)| self.synth_note(MidiNoteCmd::StopNote(note), track));
+    }
 
-        // let note = Note::from(note);
-        //
-        // match self.target {
-        //     MidiTarget::BuiltinSynth => {
-        //         self.synth.lock().unwrap().play(note, channel);
-        //     }
-        //     _ => error!("not implemented yet"),
-        // }
+    /// tells the activity monitor what a note column just did.
+    fn note_activity(&self, track: usize, column: usize, note: MidiNoteCmd) {
+        let note = match note {
+            MidiNoteCmd::PlayNote(note) => Some(note),
+            MidiNoteCmd::StopNote(_) => None,
+            MidiNoteCmd::HoldNote => return,
+        };
 
-        warn!("commands not implemented yet");
+        self.events.publish(BackendEvent::NoteActivity(NoteActivity {
+            track,
+            column,
+            note,
+        }));
     }
 
-    fn recalc_beat_time(&mut self) {
-        self.beat_time = Duration::from_nanos(NANO_MIN / self.tempo / self.beat);
+    /// sends a note off for every note that is still sounding.
+    fn notes_off(&mut self) {
+        // blank the activity monitor
+        let n_tracks = self.song.lock().unwrap().sequences.len();
+
+        (0..n_tracks).for_each(|track| {
+            (0..RowData::default().notes.len())
+                .for_each(|column| self.note_activity(track, column, MidiNoteCmd::StopNote(0)))
+        });
+
+        let active: Vec<((String, MidiChannel), HashSet<MidiNote>)> =
+            self.active_notes.drain().collect();
+
+        active.into_iter().for_each(|((dev, channel), notes)| {
+            notes
+                .into_iter()
+                .for_each(|note| self.send_note(MidiNoteCmd::StopNote(note), dev.clone(), channel))
+        });
+
+        self.synth_notes_off(None);
+        // the notes the song left sounding were just stopped with everything else.
+        self.timeline.release();
     }
 
-    fn set_tempo(&mut self, tempo: u64) {
-        if tempo != self.tempo {
-            self.tempo = tempo;
-            self.recalc_beat_time();
+    /// sends a note off for every note still sounding on the device and channel of `track`.
+    fn track_notes_off(&mut self, track: usize) {
+        self.synth_notes_off(Some(track));
+
+        let Some(key) = self
+            .song
+            .lock()
+            .unwrap()
+            .sequences
+            .get(track)
+            .map(|sequence| (sequence.dev.clone(), sequence.channel))
+        else {
+            return;
+        };
+
+        if let Some(notes) = self.active_notes.remove(&key) {
+            let (dev, channel) = key;
+
+            notes
+                .into_iter()
+                .for_each(|note| self.send_note(MidiNoteCmd::StopNote(note), dev.clone(), channel));
         }
     }
 
-    fn set_beat(&mut self, beat: u64) {
-        if beat != self.beat {
-            info!("setting beat to 1/{beat}");
-            self.beat = beat;
-            self.recalc_beat_time();
+    fn set_mute(&mut self, track: usize, mute: bool) {
+        if mute {
+            self.muted.insert(track);
+            self.track_notes_off(track);
+            (0..RowData::default().notes.len())
+                .for_each(|column| self.note_activity(track, column, MidiNoteCmd::StopNote(0)));
+        } else {
+            self.muted.remove(&track);
         }
     }
-}
 
-impl Future for Player {
-    type Output = ();
+    /// sends all notes off & all sound off on every channel of every connected output, for when
+    /// something downstream is stuck.
+    fn panic(&mut self) {
+        warn!("panic! silencing every midi output");
+        self.active_notes.clear();
+        self.synth_notes_off(None);
 
-    fn poll(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
-        let s = Pin::<&mut Player>::into_inner(self);
+        let devs: Vec<String> = self.midi_outs.keys().cloned().collect();
 
-        // read from self.ipc and do the thing it says
-        if let Ok(cmd_msg) = s.ipc.try_recv() {
-            match cmd_msg {
-                // PlayerCmd::VolumeSet((vol, channel)) => {
-                //     // if let Err(e) = s.synth.lock().unwrap().set_volume(vol, channel) {
-                //     //     error!("{e}");
-                //     // }
-                //     error!("not implemented yet");
-                // }
-                PlayerCmd::PausePlayback => match s.state {
-                    PlaybackState::Playing(line_num) => s.state = PlaybackState::Paused(line_num),
-                    PlaybackState::Paused(_) => error!("playback is already paused."),
-                    PlaybackState::NotPlaying => error!("can't pause, not playing."),
-                },
-                PlayerCmd::ResumePlayback => match s.state {
-                    PlaybackState::Playing(_) => error!("can't play while already playing."),
-                    PlaybackState::Paused(line_num) => s.state = PlaybackState::Playing(line_num),
-                    PlaybackState::NotPlaying => s.state = PlaybackState::Playing(0),
-                },
-                PlayerCmd::StopPlayback => {
-                    if let PlaybackState::NotPlaying = s.state {
-                        error!("can't stop playing while already not playing");
-                    } else {
-                        s.state = PlaybackState::NotPlaying;
-                        s.last_event = Instant::now() - Duration::from_nanos(NANO_MIN);
+        devs.into_iter().for_each(|dev| {
+            for channel in 0..16 {
+                for control in [ALL_NOTES_OFF, ALL_SOUND_OFF] {
+                    if !self.send_raw(&dev, &control_change(channel, control, 0)) {
+                        error!("sending panic to \"{dev}\" channel {} failed.", channel + 1);
                     }
                 }
-                PlayerCmd::SetCursor(loc) => match s.state {
-                    PlaybackState::Playing(_) => s.state = PlaybackState::Playing(loc),
-                    PlaybackState::Paused(_) => s.state = PlaybackState::Paused(loc),
-                    PlaybackState::NotPlaying => {
-                        error!("can't set cursor location when there is no cursor location to set.")
-                    }
-                },
-                PlayerCmd::SetTempo(tempo) => s.set_tempo(tempo),
-                PlayerCmd::SetBeat(beat) => s.set_beat(beat),
-                // PlayerCmd::SetWavetable((channel, Wavetable::BuiltIn(waveform_type))) => {
-                // if let Err(e) = s.synth.lock().unwrap().set_waveform(channel, waveform_type) {
-                //     error!(
-                //         "atempt to set channel {channel}'s synth to waveform {waveform_type:?} resulted in error, {e}"
-                //     );
-                // }
-                // }
-                // PlayerCmd::SetWavetable((_clet dur = Duration::from_millis(5);hannel, Wavetable::FromFile(_table_file))) => {
-                //     // TODO: add loading of wave table from file.
-                //     todo!("load wave table from file")
-                // }
-                PlayerCmd::SetRecHead(sequence, note_n) => {
-                    let row = s.song.lock().unwrap().sequences[0].clone();
+            }
+        });
+    }
 
-                    if sequence < row.data.len() && note_n < row.data[0].notes.len() {
-                        s.rec_head = (sequence, note_n)
-                    } else {
-                        error!("sequence: {sequence}, note: {note_n}. invalid");
-                    }
-                }
+    /// sends `bytes` to the midi output `dev` and logs it to the midi monitor. returns true if the
+    /// message was sent.
+    fn send_raw(&mut self, dev: &str, bytes: &[u8]) -> bool {
+        let Some(out) = self.midi_out(dev) else {
+            return false;
+        };
+
+        if let Err(e) = out.send(bytes) {
+            error!("tried to sending midi output message, resulted in error {e}.");
+
+            // the device was probably unplugged, reconnect the next time it's used.
+            if dev != DEFAULT_MIDI_DEV_NAME {
+                self.midi_outs.remove(dev);
             }
-        }
 
-        if let PlaybackState::Playing(line_i) = s.state {
-            // let mut last_event = s.last_event.lock().unwrap();
+            return false;
+        }
 
-            if Instant::now().duration_since(s.last_event) >= s.beat_time {
-                s.last_event = Instant::now();
+        self.monitor(MidiDirection::Out, dev, bytes);
 
-                if let Err(e) = s.line_out.send(line_i) {
-                    error!("could not send line num over internal crossbeam channel. incountered error: {e}");
-                }
+        true
+    }
 
-                // .clone()
-                // .map(|window| window.emit_all("playhead", line_i).unwrap());
+    fn monitor(&self, direction: MidiDirection, dev: &str, bytes: &[u8]) {
+        let entry = MidiMonitorEntry {
+            timestamp: self.started.elapsed().as_micros() as u64,
+            direction,
+            dev: dev.to_string(),
+            channel: message_channel(bytes),
+            bytes: bytes.to_vec(),
+        };
 
-                s.state = PlaybackState::Playing(
-                    (line_i + 1) % s.song.lock().unwrap().sequences[0].data.len(),
-                );
-                trace!("playback state: {:0X}", line_i);
+        self.events.publish(BackendEvent::Monitor(entry));
+    }
 
-                let notes: Vec<(u8, Vec<MidiNoteCmd>, String)> = s
-                    .song
-                    .lock()
-                    .unwrap()
-                    .sequences
-                    .iter()
-                    // .enumerate()
-                    .map(|sequence| {
-                        // if let Some(lines) = sequence {
-                        let row_dat = sequence.data[line_i % sequence.data.len()];
-
-                        // if let Some(MidiNoteCmd::PlayNote((note,))) = row_dat.notes[0] {
-                        //     s.notes_out.send((sequence.dev,  Some(note)));
-                        // } else if let Some(MidiNoteCmd::StopNote(_)) = row_dat.notes[0] {
-                        //     s.notes_out.send((i, None));
-                        // }
-
-                        (
-                            sequence.channel,
-                            row_dat
-                                .notes
-                                .into_iter()
-                                .filter_map(|note_cmd| note_cmd)
-                                .collect(),
-                            sequence.dev.clone(),
-                        )
-                        // } else {
-                        //     None
-                        // }
-                    })
-                    .collect();
-
-                let cmds: Vec<(usize, Vec<(Cmd, Option<CmdArg>)>)> = s
-                    .song
-                    .lock()
-                    .unwrap()
-                    .sequences
-                    .iter()
-                    .enumerate()
-                    .map(|(i, sequence)| {
-                        // if let Some(lines) = sequence {
-                        let row_dat = sequence.data[line_i % sequence.data.len()];
-
-                        (i, row_dat.cmds.into_iter().filter_map(|cmd| cmd).collect())
-                        // } else {
-                        //     None
-                        // }
-                    })
-                    .collect();
-
-                notes.into_iter().for_each(|(channel, notes, dev)| {
-                    notes
-                        .into_iter()
-                        .for_each(|note| s.send_note(note, dev.clone(), channel))
-                });
+    /// gets the connection to the midi output `dev`, connecting to it if this is the first time
+    /// it's used.
+    fn midi_out(&mut self, dev: &str) -> Option<&mut MidiOutputConnection> {
+        if !self.midi_outs.contains_key(dev) {
+            match connect_output(dev) {
+                Ok(conn) => {
+                    info!("connected to midi output \"{dev}\"");
+                    self.unknown_devs.remove(dev);
+                    self.midi_outs.insert(dev.to_string(), conn);
+                }
+                Err(e) => {
+                    if self.unknown_devs.insert(dev.to_string()) {
+                        error!("notes sent to \"{dev}\" will be dropped. {e}");
+                        self.events.error(format!("notes sent to \"{dev}\" will be dropped. {e}"));
+                    }
 
-                cmds.into_iter().for_each(|(channel, cmds)| {
-                    cmds.into_iter().for_each(|cmd| s.send_cmd(cmd, channel))
-                });
+                    return None;
+                }
             }
         }
 
-        cx.waker().wake_by_ref();
-        Poll::Pending
+        self.midi_outs.get_mut(dev)
     }
-}
 
-fn new_midi_dev(name: &str) -> anyhow::Result<MidiOutputConnection> {
-    let midi_out = MidiOutput::new("midir forwarding output")?;
+    /// applies an effect command to the built in synth voice of track `channel`. tracks played
+    /// through a midi output ignore them.
+    fn send_cmd(&mut self, command: (Cmd, Option<CmdArg>), channel: usize) {
+        let target = self.song.lock().unwrap().sequences.get(channel).map(|track| track.target);
 
-    // let out_port = select_port(&midi_out, "output")?;
+        if target != Some(MidiTarget::BuiltinSynth) {
+            return;
+        }
 
-    // let out_port_name = midi_out.port_name(&out_port)?;
-    match midi_out.create_virtual(name) {
-        Ok(dev) => Ok(dev),
-        Err(e) => bail!("{e}"),
+        match SynthCmd::from_effect(channel as ChannelIndex, command) {
+            Some(cmd) => {
+                if let Some(ref synth) = self.synth {
+                    if let Err(e) = synth.send(cmd) {
+                        error!("the {} command on track {} failed. {e}", command.0, channel + 1);
+                    }
+                }
+            }
+            None => warn!("the {} command is not implemented yet", command.0),
+        }
     }
-}
 
-#[tauri::command(rename_all = "snake_case")]
-fn send_midi(_synth: State<'_, Arc<StdMutex<TrackerState>>>, _midi_cmd: Vec<u8>) {
-    // synth.stop(note);
-    warn!("sending of generic MIDI commands is not yet implemented");
-}
+    /// sets what the oscillators of `track` play. files are loaded on their own thread so playback
+    /// isn't held up, and the audio thread never touches the disk.
+    fn set_wavetable(&self, track: ChannelIndex, wavetable: Wavetable) {
+        let Some(synth) = self.synth.clone() else {
+            return;
+        };
 
-#[tauri::command(rename_all = "snake_case")]
-async fn add_note(
-    state: State<'_, Arc<StdMutex<TrackerState>>>,
-    note: MidiNote,
-    vel: u8,
-    channel: ChannelIndex,
-    start: usize,
-    stop: usize,
-    note_number: usize,
-) -> Result<(), ()> {
-    // println!("inside add_note");
-    if let Err(e) = state.lock().map_err(|_e| ())?.add_note(
-        Some(MidiNoteCmd::PlayNote((note, vel))),
-        channel,
-        start,
-        note_number,
-    ) {
-        error!("failed to add note: {note:?}, to channel {channel}, at row {start}. this process failed with error: {e}");
+        match wavetable {
+            Wavetable::BuiltIn(waveform) => {
+                if let Err(e) = synth.set_waveform(track, waveform) {
+                    error!("could not set the waveform of track {}. {e}", track + 1);
+                }
+            }
+            Wavetable::FromFile { path, frames } => {
+                let events = self.events.clone();
+
+                thread::spawn(move || {
+                    let res = Table::load(&path, frames)
+                        .and_then(|table| synth.set_wavetable(track, Arc::new(table)));
+
+                    if let Err(e) = res {
+                        let mesg = format!(
+                            "could not load the wavetable {} for track {}. {e}",
+                            path.display(),
+                            track + 1
+                        );
+                        error!(mesg);
+                        events.error(mesg);
+                    }
+                });
+            }
+        }
     }
 
-    for i in (start + 1)..stop {
-        if let Err(e) =
-            state
-                .lock().map_err(|_e| ())?
-                .add_note(Some(MidiNoteCmd::HoldNote), channel, i, note_number)
-        {
-            error!("failed to add note: {note:?}, to channel {channel}, at row {i}. this process failed with error: {e}");
+    /// picks what `track` plays its notes with. samples are loaded on their own thread, like
+    /// wavetables.
+    fn set_instrument(&self, track: ChannelIndex, instrument: Instrument) {
+        let Some(synth) = self.synth.clone() else {
+            return;
+        };
+
+        let fm = match instrument {
+            Instrument::Fm(fm) => Some(fm),
+            _ => None,
+        };
+
+        if let Err(e) = synth.set_fm(track, fm) {
+            error!("could not set the instrument of track {}. {e}", track + 1);
         }
-    }
 
-    if let Err(e) = state.lock().map_err(|_e| ())?.add_note(
-        Some(MidiNoteCmd::StopNote(note)),
-        channel,
-        stop,
-        note_number,
-    ) {
-        error!("failed to add note: {note:?}, to channel {channel}, at row {stop}. this process failed with error: {e}");
+        match instrument {
+            Instrument::Oscillator | Instrument::Fm(_) => {
+                if let Err(e) = synth.set_sampler(track, None) {
+                    error!("could not set the instrument of track {}. {e}", track + 1);
+                }
+            }
+            Instrument::Sampler(zones) => {
+                let events = self.events.clone();
+
+                thread::spawn(move || {
+                    let res = Kit::load(&zones)
+                        .and_then(|kit| synth.set_sampler(track, Some(Arc::new(kit))));
+
+                    if let Err(e) = res {
+                        let mesg =
+                            format!("could not load the samples for track {}. {e}", track + 1);
+                        error!(mesg);
+                        events.error(mesg);
+                    }
+                });
+            }
+        }
     }
 
-    // else {WEBKIT_DISABLE_DMABUF_RENDERER=0
-    //     info!("added note {note} successfully");
-    // }
+    /// gives `track` every setting of `preset`, loading its files on their own thread like
+    /// `set_wavetable`.
+    fn load_preset(&self, track: ChannelIndex, preset: InstrumentPreset) {
+        let Some(synth) = self.synth.clone() else {
+            return;
+        };
 
-    Ok(())
-}
+        let events = self.events.clone();
 
-#[tauri::command(rename_all = "snake_case")]
-async fn rm_note(
-    state: State<'_, Arc<StdMutex<TrackerState>>>,
-    channel: ChannelIndex,
-    row: usize,
-    note_number: usize,
-) -> Result<(), ()> {
-    // println!("inside add_note");
-    if let Err(e) = state.lock().map_err(|_e| ())?.rm_note(channel, row, note_number) {
-        error!("failed to rm note on row {row}, from channel {channel}. this process failed with error: {e}");
+        thread::spawn(move || {
+            let res = preset_cmds(track, &preset)
+                .and_then(|cmds| cmds.into_iter().try_for_each(|cmd| synth.send(cmd)));
+
+            if let Err(e) = res {
+                let mesg = format!(
+                    "could not load the preset \"{}\" on track {}. {e}",
+                    preset.name,
+                    track + 1
+                );
+                error!(mesg);
+                events.error(mesg);
+            }
+        });
     }
 
-    Ok(())
-}
+    /// control changes from a midi input go to the track being recorded to, and while recording
+    /// they're written to its cc lane at the playhead.
+    fn handle_cc(&mut self, control: u8, value: u8) {
+        let track = self.rec_head.0;
 
-// #[tauri::command(rename_all = "snake_case")]
-// fn set_play_head(
-//     synth: State<'_, Arc<Mutex<Player>>>,
-//     note: Note,
-//     channel: Option<usize>,
-//     row: usize,
-// ) {
-//     // warn!("setting the play head location on the back end is not yet implemented");
-// }
+        if self.recording && matches!(self.state, PlaybackState::Playing(_)) {
+            let row = self.timeline.playhead();
+            let cc = Some((control, value));
+            let recorded = self.song.lock().unwrap().set_cc(track as ChannelIndex, row, cc);
 
-async fn line_out(window: Window, line_rx: Receiver<usize>) {
-    loop {
-        // // might not be nessesary
-        // while line_rx.len() > 1 {
-        //     line_rx.recv().unwrap();
-        // }
+            match recorded {
+                Ok(()) => self.song_changed(),
+                Err(e) => error!("failed to record cc {control} to track {}. {e}", track + 1),
+            }
+        }
 
-        while let Ok(ln) = line_rx.recv() {
-            // info!("line number {ln}");
+        let (channel, dev, target) = {
+            let song = self.song.lock().unwrap();
 
-            if let Some(window) = window.get_webview_window(WEB_VIEW_WINDOW) {
-                // info!("line number sent");
-                window.emit("playhead", ln).unwrap();
+            match song.sequences.get(track) {
+                Some(sequence) => (sequence.channel, sequence.dev.clone(), sequence.target),
+                None => return,
             }
-        }
+        };
 
-        // info!("line")
+        self.play_cc(track, control, value, target, &dev, channel);
     }
-}
 
-async fn note_out(window: Window, note_rx: Receiver<(usize, Option<MidiNote>)>) {
-    loop {
-        // // might not be nessesary
-        // while note_rx.len() > 1 {
-        //     note_rx.recv().unwrap();
-        // }
-
-        while let Ok(note_dat) = note_rx.recv() {
-            if let Some(window) = window.get_webview_window(WEB_VIEW_WINDOW) {
-                window.emit("note-change", note_dat).unwrap();
+    /// sends a control change to `track`, the built in synth follows the ones it knows.
+    fn play_cc(
+        &mut self,
+        track: usize,
+        control: u8,
+        value: u8,
+        target: MidiTarget,
+        dev: &str,
+        channel: MidiChannel,
+    ) {
+        match target {
+            MidiTarget::BuiltinSynth => {
+                let (Some(synth), Some(cmd)) = (
+                    &self.synth,
+                    SynthCmd::from_cc(track as ChannelIndex, control, value),
+                ) else {
+                    return;
+                };
+
+                if let Err(e) = synth.send(cmd) {
+                    error!("could not apply cc {control} to track {}. {e}", track + 1);
+                }
+            }
+            MidiTarget::MidiOut => {
+                if channel < 16 && !self.send_raw(dev, &control_change(channel, control, value)) {
+                    error!("sending cc {control} to \"{dev}\" channel {} failed.", channel + 1);
+                }
             }
         }
     }
-}
 
-#[tauri::command(rename_all = "snake_case")]
-async fn playback(
-    // player: State<'_, Arc<Mutex<Player>>>,
-    window: Window,
-    player_ipc: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
-    io_threads: State<'_, Arc<Mutex<Option<IO>>>>,
-    line_rx: State<'_, Receiver<usize>>,
-    note_rx: State<'_, Receiver<(usize, Option<MidiNote>)>>,
-    playback_cmd: PlaybackCmd,
-) -> Result<(), ()> {
-    // warn!("playback is not yet enabled on the back end is not yet implemented");
-    warn!("playback called. playback: {playback_cmd:?}");
-    let player_ipc = player_ipc.lock().await;
-    warn!("lock obtained. playback: {playback_cmd:?}");
-    
-    match playback_cmd {
-        PlaybackCmd::Play => {
-            if let Err(e) = player_ipc.send(PlayerCmd::ResumePlayback) {
-                error!("failed to play: {e}");
-            } else {
-                let mut threads = io_threads.lock().await;
-                warn!("lock obtained for threads.");
-                if threads.is_none() { 
-                    let line_rx = line_rx.inner().clone();
-                    let note_rx = note_rx.inner().clone();
-                    
-                    // threads.line_out = spawn(line_out(window.clone(), line_rx));
-                    // line_out(window.clone(), line_rx).await;
-
-                    // threads.note_out = spawn(note_out(window.clone(), note_rx));
-                    // note_out(window.clone(), note_rx).await;
-                    *threads =
-                        Some(IO {
-                            line_out: spawn(line_out(window.clone(), line_rx)),
-                            note_out: spawn(note_out(window.clone(), note_rx)),
-                        });
-                }
-            };
-        }
-        PlaybackCmd::Stop => {
-            if let Err(e) = player_ipc.send(PlayerCmd::StopPlayback) {
-                error!("failed to stop: {e}");
-            } else {
-                // set all join_handles back to nothing
-                // let mut threads = io_threads.lock().await;
-                // (*threads).line_out.abort();
-                // (*threads).note_out.abort();
-                //
-                // (*threads).line_out = spawn(async move { () });
-                // (*threads).note_out = spawn(async move { () });
-            };
-        }
-        PlaybackCmd::SetCursor(loc) => {
-            if let Err(e) = player_ipc.send(PlayerCmd::SetCursor(loc)) {
-                error!("failed to set cursor loction: {e}");
+    /// pans the built in synth voice of `track`.
+    fn synth_pan(&self, track: usize, pan: Float) {
+        if let Some(ref synth) = self.synth {
+            if let Err(e) = synth.set_pan(track as ChannelIndex, pan) {
+                error!("could not pan track {}. {e}", track + 1);
             }
         }
-        _ => warn!("playback is not yet enabled on the back end is not yet implemented"),
     }
-    
-    Ok(())
-}
 
-#[tauri::command(rename_all = "snake_case")]
-async fn set_tempo(player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>, tempo: u64) -> Result<(), ()> {
-    if tempo > 1 {
-        let _ = player.lock().await.send(PlayerCmd::SetTempo(tempo));
+    fn synth_sends(&self, track: usize, sends: Sends) {
+        if let Some(ref synth) = self.synth {
+            if let Err(e) = synth.set_sends(track as ChannelIndex, sends) {
+                error!("could not set the sends of track {}. {e}", track + 1);
+            }
+        }
     }
 
-    Ok(())
-}
-
-#[tauri::command(rename_all = "snake_case")]
-async fn set_beat(player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>, beat: u64) -> Result<(), ()> {
-    if beat > 1 {
-        let _ = player.lock().await.send(PlayerCmd::SetBeat(beat));
+    fn synth_fx(&self, fx: FxSettings) {
+        if let Some(ref synth) = self.synth {
+            if let Err(e) = synth.set_fx(fx) {
+                error!("could not set the effects. {e}");
+            }
+        }
     }
 
-    Ok(())
-}
+    /// sets every tracks pan & sends, and the effects, back to what's saved in the song, undoing
+    /// pan commands.
+    fn reset_mix(&self) {
+        let (mix, fx): (Vec<(Float, Sends)>, FxSettings) = {
+            let song = self.song.lock().unwrap();
 
-#[tauri::command(rename_all = "snake_case")]
-async fn get_state(
-    window: Window,
-    state: State<'_, Arc<StdMutex<TrackerState>>>,
-    start_row: usize,
-    n_rows: usize,
-) -> Result<(), ()> {
-    let tracker_state = { state.lock().map_err(|_e| ())?.copy_from_row(start_row, n_rows) };
+            (
+                song.sequences
+                    .iter()
+                    .map(|sequence| (sequence.pan, sequence.sends))
+                    .collect(),
+                song.fx,
+            )
+        };
 
-    if let Some(window) = window.get_webview_window(WEB_VIEW_WINDOW) {
-        window.emit("state-change", tracker_state).unwrap();
+        mix.into_iter().enumerate().for_each(|(track, (pan, sends))| {
+            self.synth_pan(track, pan);
+            self.synth_sends(track, sends);
+        });
+        self.synth_fx(fx);
     }
 
-    Ok(())
-}
+    /// stops playback and sets the synth up from the song that was just loaded, every track's
+    /// sound & mix, and the effects.
+    fn project_loaded(&mut self) {
+        self.stop();
+        self.reset_mix();
+
+        let presets: Vec<InstrumentPreset> = self
+            .song
+            .lock()
+            .unwrap()
+            .sequences
+            .iter()
+            .map(|sequence| sequence.preset.clone())
+            .collect();
+
+        presets
+            .into_iter()
+            .enumerate()
+            .for_each(|(track, preset)| self.load_preset(track as ChannelIndex, preset));
+    }
 
-#[tauri::command(rename_all = "snake_case")]
-async fn set_record_head(
-    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
-    sequence: usize,
-    note_n: usize,
-) -> Result<(), ()> {
-    if let Err(e) = player.lock().await.send(PlayerCmd::SetRecHead(sequence, note_n)) {
-        error!("{e}");
+    fn recalc_beat_time(&mut self) {
+        self.beat_time = Duration::from_nanos(row_nanos(self.tempo, self.beat));
     }
 
-    Ok(())
+    fn set_tempo(&mut self, tempo: u64) {
+        if tempo != self.tempo {
+            self.tempo = tempo;
+            self.recalc_beat_time();
+            self.report_clock();
+
+            if let Some(ref synth) = self.synth {
+                if let Err(e) = synth.set_tempo(tempo as Float) {
+                    error!("could not sync the delay to the tempo. {e}");
+                }
+            }
+        }
+    }
+
+    fn set_beat(&mut self, beat: u64) {
+        if beat != self.beat {
+            info!("setting beat to 1/{beat}");
+            self.beat = beat;
+            self.recalc_beat_time();
+        }
+    }
+
+    fn song_len(&self) -> usize {
+        self.song.lock().unwrap().sequences[0].data.len()
+    }
+
+    /// sends the notes and commands of row `line_i` and tells the ui where the playhead is.
+    fn play_row(&mut self, line_i: usize) {
+        self.events.publish(BackendEvent::Playhead(line_i));
+
+        trace!("playback state: {:0X}", line_i);
+
+        let (events, tracks): (Vec<RowEvent>, Vec<(u8, String, MidiTarget)>) = {
+            let song = self.song.lock().unwrap();
+
+            (
+                self.timeline.step(&song, line_i, |track| self.muted.contains(&track)),
+                song.sequences
+                    .iter()
+                    .map(|sequence| (sequence.channel, sequence.dev.clone(), sequence.target))
+                    .collect(),
+            )
+        };
+
+        events.into_iter().for_each(|event| match event {
+            RowEvent::Note {
+                track,
+                column,
+                note,
+            } => {
+                let (channel, ref dev, target) = tracks[track];
+
+                match target {
+                    MidiTarget::BuiltinSynth => self.synth_note(note, track),
+                    MidiTarget::MidiOut => self.send_note(note, dev.clone(), channel),
+                }
+
+                self.note_activity(track, column, note);
+            }
+            RowEvent::Cc {
+                track,
+                control,
+                value,
+            } => {
+                let (channel, ref dev, target) = tracks[track];
+
+                self.play_cc(track, control, value, target, dev, channel);
+            }
+            RowEvent::Cmd { track, cmd } => self.send_cmd(cmd, track),
+        });
+    }
+
+    /// connects to the midi input `dev` if it isn't already connected.
+    fn connect_midi_in(&mut self, dev: &str) -> anyhow::Result<()> {
+        self.wanted_ins.insert(dev.to_string());
+
+        if !self.midi_ins.contains_key(dev) {
+            let conn = connect_input(dev, self.midi_in_tx.clone())?;
+            info!("connected to midi input \"{dev}\"");
+            self.midi_ins.insert(dev.to_string(), conn);
+        }
+
+        Ok(())
+    }
+
+    /// drops connections to devices that were unplugged and reconnects the ones that came back.
+    fn devices_changed(&mut self, devices: MidiDevices) {
+        self.midi_outs
+            .retain(|dev, _| dev == DEFAULT_MIDI_DEV_NAME || devices.outputs.contains(dev));
+        self.midi_ins.retain(|dev, _| devices.inputs.contains(dev));
+        self.unknown_devs.clear();
+
+        let track_devs: HashSet<String> = self
+            .song
+            .lock()
+            .unwrap()
+            .sequences
+            .iter()
+            .map(|sequence| sequence.dev.clone())
+            .filter(|dev| devices.outputs.contains(dev))
+            .collect();
+
+        track_devs.into_iter().for_each(|dev| {
+            // connects if not already connected
+            _ = self.midi_out(&dev);
+        });
+
+        let returned: Vec<String> = self
+            .wanted_ins
+            .iter()
+            .filter(|dev| devices.inputs.contains(dev) && !self.midi_ins.contains_key(*dev))
+            .cloned()
+            .collect();
+
+        returned.into_iter().for_each(|dev| {
+            if let Err(e) = self.connect_midi_in(&dev) {
+                error!("can't reconnect to midi input \"{dev}\". {e}");
+            }
+        });
+    }
+
+    fn set_clock_source(&mut self, source: ClockSource) {
+        match source {
+            ClockSource::Internal => {
+                if let Some(clock) = self.clock.take() {
+                    info!("no longer following the midi clock from \"{}\"", clock.dev);
+                }
+            }
+            ClockSource::External(dev) => {
+                if let Err(e) = self.connect_midi_in(&dev) {
+                    error!("can't follow the midi clock from \"{dev}\". {e}");
+                    return;
+                }
+
+                info!("following the midi clock from \"{dev}\"");
+                self.clock = Some(ExternalClock::new(dev));
+            }
+        }
+
+        self.report_clock();
+    }
+
+    fn report_clock(&self) {
+        let status = match self.clock {
+            Some(ref clock) => ClockStatus {
+                source: ClockSource::External(clock.dev.clone()),
+                bpm: clock.bpm(),
+                running: clock.running,
+            },
+            None => ClockStatus {
+                source: ClockSource::Internal,
+                bpm: Some(self.tempo as Float),
+                running: matches!(self.state, PlaybackState::Playing(_)),
+            },
+        };
+
+        self.events.publish(BackendEvent::Clock(status));
+    }
+
+    fn set_recording(&mut self, recording: bool) {
+        if !recording {
+            self.end_recorded_notes();
+        }
+
+        info!("recording armed: {recording}");
+        self.recording = recording;
+    }
+
+    /// anything still held down ends where recording (or playback) stopped.
+    fn end_recorded_notes(&mut self) {
+        let held: Vec<MidiNote> = self.rec_notes.keys().copied().collect();
+        held.into_iter().for_each(|note| self.record_note_off(note));
+    }
+
+    fn send_keyboard(&self, event: KeyboardEvent) {
+        self.events.publish(BackendEvent::Keyboard(event));
+    }
+
+    /// sends the cells that were just recorded to the ui.
+    fn song_changed(&self) {
+        let delta = self.song.lock().unwrap().take_delta();
+
+        self.events.publish(BackendEvent::Delta(delta));
+    }
+
+    /// writes the start of a note at the playhead, in the record heads sequence. if the record
+    /// heads note column is already used by a held note the next free column is used so chords can
+    /// be recorded.
+    fn record_note_on(&mut self, note: MidiNote, vel: u8) {
+        let (sequence, note_n) = self.rec_head;
+        let row = self.timeline.playhead();
+        let n_cols = RowData::default().notes.len();
+
+        let Some(col) = (0..n_cols)
+            .map(|i| (note_n + i) % n_cols)
+            .find(|col| !self.rec_notes.values().any(|(_, used)| used == col))
+        else {
+            warn!("all note columns are in use, not recording {note}");
+            return;
+        };
+
+        if let Err(e) = self.song.lock().unwrap().add_note(
+            Some(MidiNoteCmd::PlayNote((note, vel))),
+            sequence as ChannelIndex,
+            row,
+            col,
+        ) {
+            error!("failed to record note: {note}, to sequence {sequence}, at row {row}. this process failed with error: {e}");
+            return;
+        }
+
+        self.rec_notes.insert(note, (row, col));
+        self.song_changed();
+    }
+
+    /// ends a recorded note at the playhead, filling the rows in between with holds.
+    fn record_note_off(&mut self, note: MidiNote) {
+        let Some((start, col)) = self.rec_notes.remove(&note) else {
+            return;
+        };
+
+        let sequence = self.rec_head.0 as ChannelIndex;
+        let song_len = self.song_len();
+        // a note is at least one row long.
+        let stop = if self.timeline.playhead() == start {
+            (start + 1) % song_len
+        } else {
+            self.timeline.playhead()
+        };
+
+        {
+            let mut song = self.song.lock().unwrap();
+            let mut row = (start + 1) % song_len;
+
+            while row != stop {
+                if let Err(e) = song.add_note(Some(MidiNoteCmd::HoldNote), sequence, row, col) {
+                    error!("failed to record note: {note}, to sequence {sequence}, at row {row}. this process failed with error: {e}");
+                }
+
+                row = (row + 1) % song_len;
+            }
+
+            if let Err(e) = song.add_note(Some(MidiNoteCmd::StopNote(note)), sequence, stop, col) {
+                error!("failed to record note: {note}, to sequence {sequence}, at row {stop}. this process failed with error: {e}");
+            }
+        }
+
+        self.song_changed();
+    }
+
+    /// starts playing from `row`, whatever the state of playback. the row is played straight away.
+    fn play_from(&mut self, row: usize) {
+        if matches!(self.state, PlaybackState::Playing(_)) {
+            self.end_recorded_notes();
+        }
+
+        let row = row % self.song_len();
+
+        self.notes_off();
+        self.reset_mix();
+        self.timeline.seek(row);
+        self.state = PlaybackState::Playing(row);
+        self.last_event = Instant::now() - self.beat_time;
+    }
+
+    /// stops playback and rewinds to the start of the song, stopping twice just rewinds.
+    fn stop(&mut self) {
+        if !matches!(self.state, PlaybackState::Stopped(_)) {
+            self.end_recorded_notes();
+            self.notes_off();
+            self.state = PlaybackState::Stopped(0);
+            self.last_event = Instant::now() - Duration::from_nanos(NANO_MIN);
+        }
+
+        self.move_playhead(0);
+    }
+
+    /// moves the playhead to `row` without starting or stopping playback.
+    fn move_playhead(&mut self, row: usize) {
+        let row = row % self.song_len();
+
+        self.state = match self.state {
+            PlaybackState::Playing(_) => {
+                self.end_recorded_notes();
+                self.notes_off();
+                self.timeline.seek(row);
+
+                PlaybackState::Playing(row)
+            }
+            PlaybackState::Paused(_) => PlaybackState::Paused(row),
+            PlaybackState::Stopped(_) => PlaybackState::Stopped(row),
+        };
+
+        // while playing the row is sent when it's played.
+        if !matches!(self.state, PlaybackState::Playing(_)) {
+            self.events.publish(BackendEvent::Playhead(row));
+        }
+    }
+
+    /// handles a message from a midi input.
+    fn handle_midi_in(&mut self, event: MidiInEvent) {
+        self.monitor(MidiDirection::In, &event.dev, &event.bytes);
+
+        match event.msg {
+            MidiInMsg::NoteOn { note, vel, .. } => {
+                if !matches!(self.state, PlaybackState::Playing(_)) {
+                    self.send_keyboard(KeyboardEvent::Pressed((note, vel)));
+                } else if self.recording {
+                    self.record_note_on(note, vel);
+                }
+
+                return;
+            }
+            MidiInMsg::NoteOff { note, .. } => {
+                if self.rec_notes.contains_key(&note) {
+                    self.record_note_off(note);
+                } else if !matches!(self.state, PlaybackState::Playing(_)) {
+                    self.send_keyboard(KeyboardEvent::Released(note));
+                }
+
+                return;
+            }
+            MidiInMsg::ControlChange { control, value, .. } => {
+                self.handle_cc(control, value);
+
+                return;
+            }
+            _ => {}
+        }
+
+        let beat = self.beat;
+        let song_len = self.song_len();
+
+        let Some(clock) = self.clock.as_mut().filter(|clock| clock.dev == event.dev) else {
+            return;
+        };
+
+        match event.msg {
+            MidiInMsg::Clock => {
+                let row = clock.tick(event.timestamp, beat);
+                let report = clock.should_report();
+
+                if let Some(row) = row {
+                    let row = row % song_len;
+                    self.state = PlaybackState::Playing((row + 1) % song_len);
+                    self.play_row(row);
+                }
+
+                if report {
+                    self.report_clock();
+                }
+            }
+            MidiInMsg::Start => {
+                clock.start();
+                // the row is played on the first tick after the start message.
+                self.play_from(0);
+                self.report_clock();
+            }
+            MidiInMsg::Continue => {
+                clock.resume();
+
+                if let PlaybackState::Paused(line_num) | PlaybackState::Stopped(line_num) =
+                    self.state
+                {
+                    self.state = PlaybackState::Playing(line_num);
+                }
+
+                self.report_clock();
+            }
+            MidiInMsg::Stop => {
+                clock.stop();
+                self.stop();
+                self.report_clock();
+            }
+            MidiInMsg::SongPosition(midi_beats) => {
+                let row = clock.set_position(midi_beats, beat) % song_len;
+                self.notes_off();
+
+                self.state = match self.state {
+                    PlaybackState::Playing(_) => PlaybackState::Playing(row),
+                    _ => PlaybackState::Paused(row),
+                };
+
+                self.events.publish(BackendEvent::Playhead(row));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Future for Player {
+    type Output = ();
+
+    fn poll(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let s = Pin::<&mut Player>::into_inner(self);
+
+        // read from self.ipc and do the thing it says
+        if let Ok(cmd_msg) = s.ipc.try_recv() {
+            match cmd_msg {
+                // PlayerCmd::VolumeSet((vol, channel)) => {
+                //     // if let Err(e) = s.synth.lock().unwrap().set_volume(vol, channel) {
+                //     //     error!("{e}");
+                //     // }
+                //     error!("not implemented yet");
+                // }
+                PlayerCmd::PausePlayback => match s.state {
+                    PlaybackState::Playing(line_num) => {
+                        s.end_recorded_notes();
+                        s.notes_off();
+                        s.state = PlaybackState::Paused(line_num);
+                    }
+                    PlaybackState::Paused(_) => error!("playback is already paused."),
+                    PlaybackState::Stopped(_) => error!("can't pause, not playing."),
+                },
+                PlayerCmd::ResumePlayback => match s.state {
+                    PlaybackState::Playing(_) => error!("can't play while already playing."),
+                    PlaybackState::Paused(line_num) | PlaybackState::Stopped(line_num) => {
+                        s.play_from(line_num)
+                    }
+                },
+                PlayerCmd::StopPlayback => s.stop(),
+                PlayerCmd::RestartPlayback => s.play_from(0),
+                PlayerCmd::PlayFrom(loc) => s.play_from(loc),
+                PlayerCmd::SetCursor(loc) => s.move_playhead(loc),
+                PlayerCmd::SetTempo(tempo) => s.set_tempo(tempo),
+                PlayerCmd::SetBeat(beat) => s.set_beat(beat),
+                PlayerCmd::SetWavetable((track, wavetable)) => s.set_wavetable(track, wavetable),
+                PlayerCmd::SetWavetablePos(track, position) => {
+                    if let Some(ref synth) = s.synth {
+                        if let Err(e) = synth.set_wavetable_pos(track, position) {
+                            error!("could not move the wavetable of track {}. {e}", track + 1);
+                        }
+                    }
+                }
+                PlayerCmd::SetInstrument(track, instrument) => s.set_instrument(track, instrument),
+                PlayerCmd::SetSends(track, sends) => s.synth_sends(track as usize, sends),
+                PlayerCmd::SetFx(fx) => s.synth_fx(fx),
+                PlayerCmd::ProjectLoaded => s.project_loaded(),
+                PlayerCmd::SetVelocity(track, velocity) => {
+                    if let Some(ref synth) = s.synth {
+                        if let Err(e) = synth.set_velocity(track, velocity) {
+                            error!("could not set the velocity curve of track {}. {e}", track + 1);
+                        }
+                    }
+                }
+                PlayerCmd::SetGlide(track, glide) => {
+                    if let Some(ref synth) = s.synth {
+                        if let Err(e) = synth.set_glide(track, glide) {
+                            error!("could not set the glide of track {}. {e}", track + 1);
+                        }
+                    }
+                }
+                PlayerCmd::SetVoices(track, voices) => {
+                    if let Some(ref synth) = s.synth {
+                        if let Err(e) = synth.set_voices(track, voices) {
+                            error!("could not set the voices of track {}. {e}", track + 1);
+                        }
+                    }
+                }
+                PlayerCmd::SetModulation(track, modulation) => {
+                    if let Some(ref synth) = s.synth {
+                        if let Err(e) = synth.set_modulation(track, modulation) {
+                            error!("could not set the modulation of track {}. {e}", track + 1);
+                        }
+                    }
+                }
+                PlayerCmd::LoadPreset(track, preset) => s.load_preset(track, preset),
+                PlayerCmd::SetRecHead(sequence, note_n) => {
+                    let n_sequences = s.song.lock().unwrap().sequences.len();
+
+                    if sequence < n_sequences && note_n < RowData::default().notes.len() {
+                        s.rec_head = (sequence, note_n)
+                    } else {
+                        error!("sequence: {sequence}, note: {note_n}. invalid");
+                    }
+                }
+                PlayerCmd::SetClockSource(source) => s.set_clock_source(source),
+                PlayerCmd::ConnectMidiIn(dev) => {
+                    if let Err(e) = s.connect_midi_in(&dev) {
+                        error!("can't connect to midi input \"{dev}\". {e}");
+                        s.events.error(format!("can't connect to midi input \"{dev}\". {e}"));
+                    }
+                }
+                PlayerCmd::SetRecording(recording) => s.set_recording(recording),
+                PlayerCmd::MidiDevicesChanged(devices) => s.devices_changed(devices),
+                PlayerCmd::SetMute(track, mute) => s.set_mute(track as usize, mute),
+                PlayerCmd::Panic => s.panic(),
+                PlayerCmd::TrackNotesOff(track) => s.track_notes_off(track as usize),
+                PlayerCmd::SendMidi(dev, bytes) => {
+                    if !s.send_raw(&dev, &bytes) {
+                        error!("could not send {bytes:02X?} to \"{dev}\"");
+                        s.events.error(format!("could not send {bytes:02X?} to \"{dev}\""));
+                    }
+                }
+                PlayerCmd::SetPan(track, pan) => s.synth_pan(track as usize, pan),
+                PlayerCmd::SetEnvelope(track, adsr) => {
+                    if let Some(ref synth) = s.synth {
+                        if let Err(e) = synth.set_envelope(track, adsr) {
+                            error!("could not set the envelope of track {}. {e}", track + 1);
+                        }
+                    }
+                }
+                PlayerCmd::SetFilter(track, filter) => {
+                    if let Some(ref synth) = s.synth {
+                        if let Err(e) = synth.set_filter(track, filter) {
+                            error!("could not set the filter of track {}. {e}", track + 1);
+                        }
+                    }
+                }
+            }
+        }
+
+        while let Ok(event) = s.midi_in_rx.try_recv() {
+            s.handle_midi_in(event);
+        }
+
+        if let PlaybackState::Playing(line_i) = s.state {
+            // when following an external clock rows are played as clock ticks arrive.
+            if s.clock.is_none() && Instant::now().duration_since(s.last_event) >= s.beat_time {
+                s.last_event = Instant::now();
+                s.state = PlaybackState::Playing((line_i + 1) % s.song_len());
+                s.play_row(line_i);
+            }
+        }
+
+        if s.shared != s.state {
+            *s.shared_state.lock().unwrap() = s.state;
+            s.shared = s.state;
+        }
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn send_midi(
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    dev: String,
+    midi_cmd: Vec<u8>,
+) -> Result<(), String> {
+    validate_midi(&midi_cmd).map_err(|e| {
+        error!("not sending {midi_cmd:02X?} to \"{dev}\". {e}");
+        e.to_string()
+    })?;
+
+    if !list_output_ports().map_err(|e| e.to_string())?.contains(&dev) {
+        return Err(format!("there is no midi output named \"{dev}\"."));
+    }
+
+    player
+        .lock()
+        .await
+        .send(PlayerCmd::SendMidi(dev, midi_cmd))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn add_note(
+    events: State<'_, EventBus>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    note: MidiNote,
+    vel: u8,
+    channel: ChannelIndex,
+    start: usize,
+    stop: usize,
+    note_number: usize,
+) -> Result<StateDelta, ()> {
+    // println!("inside add_note");
+    // held for the whole note so the edit lands in one delta.
+    let mut state = state.lock().map_err(|_e| ())?;
+
+    if let Err(e) = state.add_note(
+        Some(MidiNoteCmd::PlayNote((note, vel))),
+        channel,
+        start,
+        note_number,
+    ) {
+        error!("failed to add note: {note:?}, to channel {channel}, at row {start}. this process failed with error: {e}");
+    }
+
+    for i in (start + 1)..stop {
+        if let Err(e) = state.add_note(Some(MidiNoteCmd::HoldNote), channel, i, note_number) {
+            error!("failed to add note: {note:?}, to channel {channel}, at row {i}. this process failed with error: {e}");
+        }
+    }
+
+    if let Err(e) = state.add_note(
+        Some(MidiNoteCmd::StopNote(note)),
+        channel,
+        stop,
+        note_number,
+    ) {
+        error!("failed to add note: {note:?}, to channel {channel}, at row {stop}. this process failed with error: {e}");
+    }
+
+    // else {WEBKIT_DISABLE_DMABUF_RENDERER=0
+    //     info!("added note {note} successfully");
+    // }
+
+    let delta = state.take_delta();
+    events.publish(BackendEvent::Delta(delta.clone()));
+
+    Ok(delta)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn rm_note(
+    events: State<'_, EventBus>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    channel: ChannelIndex,
+    row: usize,
+    note_number: usize,
+) -> Result<StateDelta, ()> {
+    // println!("inside add_note");
+    let mut state = state.lock().map_err(|_e| ())?;
+
+    if let Err(e) = state.rm_note(channel, row, note_number) {
+        error!("failed to rm note on row {row}, from channel {channel}. this process failed with error: {e}");
+    }
+
+    let delta = state.take_delta();
+    events.publish(BackendEvent::Delta(delta.clone()));
+
+    Ok(delta)
+}
+
+// #[tauri::command(rename_all = "snake_case")]
+// fn set_play_head(
+//     synth: State<'_, Arc<Mutex<Player>>>,
+//     note: Note,
+//     channel: Option<usize>,
+//     row: usize,
+// ) {
+//     // warn!("setting the play head location on the back end is not yet implemented");
+// }
+
+#[tauri::command(rename_all = "snake_case")]
+async fn playback(
+    // player: State<'_, Arc<Mutex<Player>>>,
+    player_ipc: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    playback_cmd: PlaybackCmd,
+) -> Result<(), ()> {
+    // warn!("playback is not yet enabled on the back end is not yet implemented");
+    warn!("playback called. playback: {playback_cmd:?}");
+    let player_ipc = player_ipc.lock().await;
+    warn!("lock obtained. playback: {playback_cmd:?}");
+    
+    let cmd = match playback_cmd {
+        PlaybackCmd::Play => PlayerCmd::ResumePlayback,
+        PlaybackCmd::Pause => PlayerCmd::PausePlayback,
+        PlaybackCmd::Stop => PlayerCmd::StopPlayback,
+        PlaybackCmd::Restart => PlayerCmd::RestartPlayback,
+        PlaybackCmd::SetCursor(loc) => PlayerCmd::SetCursor(loc),
+        PlaybackCmd::PlayFrom(loc) => PlayerCmd::PlayFrom(loc),
+    };
+
+    if let Err(e) = player_ipc.send(cmd) {
+        error!("failed to send playback command {playback_cmd:?}: {e}");
+        return Err(());
+    }
+
+    Ok(())
+}
+
+/// the current state of playback, so the ui can put the playhead back where it was after a reload.
+#[tauri::command(rename_all = "snake_case")]
+async fn get_playback_state(
+    playback_state: State<'_, Arc<StdMutex<PlaybackState>>>,
+) -> Result<PlaybackState, ()> {
+    Ok(*playback_state.lock().map_err(|_e| ())?)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_tempo(player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>, tempo: u64) -> Result<(), ()> {
+    if tempo > 1 {
+        let _ = player.lock().await.send(PlayerCmd::SetTempo(tempo));
+    }
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_beat(player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>, beat: u64) -> Result<(), ()> {
+    if beat > 1 {
+        let _ = player.lock().await.send(PlayerCmd::SetBeat(beat));
+    }
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn get_state(
+    events: State<'_, EventBus>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    start_row: usize,
+    n_rows: usize,
+) -> Result<(), ()> {
+    *view.lock().map_err(|_e| ())? = ViewWindow { start_row, n_rows };
+    let tracker_state = { state.lock().map_err(|_e| ())?.copy_from_row(start_row, n_rows) };
+
+    events.publish(BackendEvent::State(tracker_state));
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn list_midi_inputs() -> Result<Vec<String>, String> {
+    list_input_ports().map_err(|e| {
+        error!("listing midi inputs failed with error: {e}");
+        e.to_string()
+    })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_clock_source(
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    source: ClockSource,
+) -> Result<(), ()> {
+    if let Err(e) = player.lock().await.send(PlayerCmd::SetClockSource(source)) {
+        error!("{e}");
+    }
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_mute(
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    track: ChannelIndex,
+    mute: bool,
+) -> Result<(), ()> {
+    if let Err(e) = player.lock().await.send(PlayerCmd::SetMute(track, mute)) {
+        error!("{e}");
+    }
+
+    Ok(())
+}
+
+/// records an edit to the preset of `track` in the song, then sends the player `cmd` to make the
+/// synth sound like it.
+async fn edit_preset(
+    events: &EventBus,
+    player: &Mutex<Sender<PlayerCmd>>,
+    state: &StdMutex<TrackerState>,
+    view: &StdMutex<ViewWindow>,
+    track: ChannelIndex,
+    cmd: PlayerCmd,
+    edit: impl FnOnce(&mut InstrumentPreset),
+) -> Result<(), String> {
+    let tracker_state = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+
+        let Some(sequence) = state.sequences.get_mut(track as usize) else {
+            return Err(format!("track {} does not exist.", track + 1));
+        };
+
+        edit(&mut sequence.preset);
+
+        let ViewWindow { start_row, n_rows } = *view.lock().map_err(|e| e.to_string())?;
+        state.copy_from_row(start_row, n_rows)
+    };
+
+    player.lock().await.send(cmd).map_err(|e| e.to_string())?;
+
+    events.publish(BackendEvent::State(tracker_state));
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_envelope(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    adsr: Adsr,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetEnvelope(track, adsr);
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.envelope = adsr;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_filter(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    filter: FilterSettings,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetFilter(track, filter);
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.filter = filter;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_velocity(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    velocity: VelocitySettings,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetVelocity(track, velocity);
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.velocity = velocity;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_voices(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    voices: VoiceSettings,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetVoices(track, voices);
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.voices = voices;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_glide(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    glide: GlideSettings,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetGlide(track, glide);
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.glide = glide;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_wavetable(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    wavetable: Wavetable,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetWavetable((track, wavetable.clone()));
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.wavetable = wavetable;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_wavetable_pos(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    position: Float,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetWavetablePos(track, position);
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.wavetable_pos = position;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_instrument(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    instrument: Instrument,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetInstrument(track, instrument.clone());
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.instrument = instrument;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn panic(player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>) -> Result<(), ()> {
+    if let Err(e) = player.lock().await.send(PlayerCmd::Panic) {
+        error!("{e}");
+    }
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn list_midi_outputs() -> Result<Vec<String>, String> {
+    list_output_ports().map_err(|e| {
+        error!("listing midi outputs failed with error: {e}");
+        e.to_string()
+    })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_device(
+    events: State<'_, EventBus>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    dev: String,
+    channel: MidiChannel,
+) -> Result<(), String> {
+    if channel >= 16 {
+        return Err(format!("midi channel {} does not exist.", channel + 1));
+    }
+
+    let ports = list_output_ports().map_err(|e| e.to_string())?;
+
+    if !ports.contains(&dev) {
+        let mesg = format!("there is no midi output named \"{dev}\".");
+        error!(mesg);
+        return Err(mesg);
+    }
+
+    let tracker_state = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+
+        let Some(sequence) = state.sequences.get_mut(track as usize) else {
+            return Err(format!("track {} does not exist.", track + 1));
+        };
+
+        info!("track {track} now plays on \"{dev}\" channel {}", channel + 1);
+        sequence.dev = dev;
+        sequence.channel = channel;
+
+        let ViewWindow { start_row, n_rows } = *view.lock().map_err(|e| e.to_string())?;
+        state.copy_from_row(start_row, n_rows)
+    };
+
+    events.publish(BackendEvent::State(tracker_state));
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_target(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    target: MidiTarget,
+) -> Result<(), String> {
+    // notes playing on the old target would never get their note off.
+    player
+        .lock()
+        .await
+        .send(PlayerCmd::TrackNotesOff(track))
+        .map_err(|e| e.to_string())?;
+
+    let tracker_state = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+
+        let Some(sequence) = state.sequences.get_mut(track as usize) else {
+            return Err(format!("track {} does not exist.", track + 1));
+        };
+
+        info!("track {track} now plays through {target:?}");
+        sequence.target = target;
+
+        let ViewWindow { start_row, n_rows } = *view.lock().map_err(|e| e.to_string())?;
+        state.copy_from_row(start_row, n_rows)
+    };
+
+    events.publish(BackendEvent::State(tracker_state));
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_pan(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    pan: Float,
+) -> Result<(), String> {
+    let pan = pan.clamp(-1.0, 1.0);
+
+    let tracker_state = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+
+        let Some(sequence) = state.sequences.get_mut(track as usize) else {
+            return Err(format!("track {} does not exist.", track + 1));
+        };
+
+        sequence.pan = pan;
+
+        let ViewWindow { start_row, n_rows } = *view.lock().map_err(|e| e.to_string())?;
+        state.copy_from_row(start_row, n_rows)
+    };
+
+    player
+        .lock()
+        .await
+        .send(PlayerCmd::SetPan(track, pan))
+        .map_err(|e| e.to_string())?;
+
+    events.publish(BackendEvent::State(tracker_state));
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_sends(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    sends: Sends,
+) -> Result<(), String> {
+    let sends = Sends {
+        delay: sends.delay.clamp(0.0, 1.0),
+        reverb: sends.reverb.clamp(0.0, 1.0),
+        chorus: sends.chorus.clamp(0.0, 1.0),
+    };
+
+    let tracker_state = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+
+        let Some(sequence) = state.sequences.get_mut(track as usize) else {
+            return Err(format!("track {} does not exist.", track + 1));
+        };
+
+        sequence.sends = sends;
+
+        let ViewWindow { start_row, n_rows } = *view.lock().map_err(|e| e.to_string())?;
+        state.copy_from_row(start_row, n_rows)
+    };
+
+    player
+        .lock()
+        .await
+        .send(PlayerCmd::SetSends(track, sends))
+        .map_err(|e| e.to_string())?;
+
+    events.publish(BackendEvent::State(tracker_state));
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_fx(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    fx: FxSettings,
+) -> Result<(), String> {
+    let tracker_state = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+        state.fx = fx;
+
+        let ViewWindow { start_row, n_rows } = *view.lock().map_err(|e| e.to_string())?;
+        state.copy_from_row(start_row, n_rows)
+    };
+
+    player
+        .lock()
+        .await
+        .send(PlayerCmd::SetFx(fx))
+        .map_err(|e| e.to_string())?;
+
+    events.publish(BackendEvent::State(tracker_state));
+
+    Ok(())
+}
+
+/// writes the whole song to `path`, its tracks, their sounds & mix, and the effects.
+#[tauri::command(rename_all = "snake_case")]
+async fn save_project(
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    path: String,
+) -> Result<(), String> {
+    let json = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+
+        serde_json::to_string_pretty(&*state).map_err(|e| e.to_string())?
+    };
+
+    fs::write(&path, json).map_err(|e| format!("writing {path}. {e}"))
+}
+
+/// replaces the song with the project saved at `path`, playback stops and the built in synth is
+/// set up the way the project was saved.
+#[tauri::command(rename_all = "snake_case")]
+async fn load_project(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    path: String,
+) -> Result<(), String> {
+    let json = fs::read_to_string(&path).map_err(|e| format!("reading {path}. {e}"))?;
+    let mut song: TrackerState =
+        serde_json::from_str(&json).map_err(|e| format!("{path} is not a project. {e}"))?;
+
+    let tracker_state = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+        // the ui goes by the revision, so it carries on from the song that was replaced.
+        song.revision = state.revision + 1;
+        *state = song;
+
+        let ViewWindow { start_row, n_rows } = *view.lock().map_err(|e| e.to_string())?;
+        state.copy_from_row(start_row, n_rows)
+    };
+
+    player
+        .lock()
+        .await
+        .send(PlayerCmd::ProjectLoaded)
+        .map_err(|e| e.to_string())?;
+
+    events.publish(BackendEvent::State(tracker_state));
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_track_modulation(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    modulation: ModMatrix,
+) -> Result<(), String> {
+    let cmd = PlayerCmd::SetModulation(track, modulation.clone());
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |preset| {
+        preset.modulation = modulation;
+    })
+    .await
+}
+
+/// writes the preset of `track` to `path` so it can be loaded into other songs.
+#[tauri::command(rename_all = "snake_case")]
+async fn save_preset(
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    track: ChannelIndex,
+    path: String,
+) -> Result<(), String> {
+    let preset = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+
+        let Some(sequence) = state.sequences.get(track as usize) else {
+            return Err(format!("track {} does not exist.", track + 1));
+        };
+
+        sequence.preset.clone()
+    };
+
+    write_preset(&preset, &path).map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn load_preset(
+    events: State<'_, EventBus>,
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    state: State<'_, Arc<StdMutex<TrackerState>>>,
+    view: State<'_, Arc<StdMutex<ViewWindow>>>,
+    track: ChannelIndex,
+    path: String,
+) -> Result<(), String> {
+    let preset = read_preset(&path).map_err(|e| e.to_string())?;
+    let cmd = PlayerCmd::LoadPreset(track, preset.clone());
+
+    edit_preset(&events, &player, &state, &view, track, cmd, |current| {
+        *current = preset;
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn connect_midi_input(
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    dev: String,
+) -> Result<(), ()> {
+    if let Err(e) = player.lock().await.send(PlayerCmd::ConnectMidiIn(dev)) {
+        error!("{e}");
+    }
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_recording(
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    recording: bool,
+) -> Result<(), ()> {
+    if let Err(e) = player.lock().await.send(PlayerCmd::SetRecording(recording)) {
+        error!("{e}");
+    }
+
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn set_record_head(
+    player: State<'_, Arc<Mutex<Sender<PlayerCmd>>>>,
+    sequence: usize,
+    note_n: usize,
+) -> Result<(), ()> {
+    if let Err(e) = player.lock().await.send(PlayerCmd::SetRecHead(sequence, note_n)) {
+        error!("{e}");
+    }
+
+    Ok(())
 }
 
 pub fn start_logging() -> anyhow::Result<()> {
@@ -625,42 +1949,70 @@ pub fn start_logging() -> anyhow::Result<()> {
 // #[tokio::main(flavor = "multi_thread", worker_threads = 2)]
 // async fn main() -> Result<()> {
 fn main() -> anyhow::Result<()> {
-    // let (synth, stream_handle, audio) = match init_synth() {
-    //     Ok((synth, stream_handle, audio)) => (synth, stream_handle, audio),
-    //     Err(e) => {
-    //         error!("{e}");
-    //         bail!("{e}");
-    //     }
-    // };
-    //
-    // info!("starting audio stream");
-
     if let Err(e) = start_logging() {
         eprintln!("{e} no logging");
     }
-    
-    // stream_handle.play_raw(audio).unwrap();
+
     info!("initializing tracker state");
     let state = Arc::new(StdMutex::new(TrackerState::default()));
+    let n_tracks = state.lock().unwrap().sequences.len();
+
+    // midi outputs still work without an audio device, so a missing one isn't fatal.
+    let synth = match init_synth(n_tracks) {
+        Ok((synth, stream_handle, audio)) => {
+            info!("starting audio stream");
+
+            match stream_handle.play_raw(audio) {
+                Ok(()) => Some(synth),
+                Err(e) => {
+                    error!("could not start the built in synth. {e}");
+                    None
+                }
+            }
+        }
+        Err(e) => {
+            error!("the built in synth is disabled. {e}");
+            None
+        }
+    };
+
+    let playback_state = Arc::new(StdMutex::new(PlaybackState::default()));
+
+    let view = Arc::new(StdMutex::new(ViewWindow::default()));
+    let (events, events_rx) = EventBus::new();
 
     info!("initializing player");
-    let (player, (player_ipc, line_rx, note_rx)) = Player::new(state.clone());
+    let (player, player_ipc) =
+        Player::new(state.clone(), playback_state.clone(), events.clone(), synth);
+    let monitor_ipc = player_ipc.clone();
     let player_ipc = Arc::new(Mutex::new(player_ipc));
     let _midi_threthreads = spawn(player);
-    let io: Arc<Mutex<Option<IO>>> = Arc::new(Mutex::new(None));
+    let monitor_events = events.clone();
 
     tauri::Builder::default()
+        .setup(move |app| {
+            events::forward_events(app.handle().clone(), events_rx);
+            devices::monitor_devices(monitor_events, monitor_ipc);
+
+            Ok(())
+        })
         // .manage(synth)
         // .manage(Arc::new(Mutex::new(player)))
         .manage(state)
+        .manage(playback_state)
         .manage(player_ipc)
-        .manage(io)
-        .manage(line_rx)
-        .manage(note_rx)
+        .manage(events)
+        .manage(view)
         .invoke_handler(tauri::generate_handler![
             // play_note,
             // stop_note,
-            send_midi, playback, add_note, get_state, rm_note, set_tempo, set_beat, set_record_head
+            send_midi, playback, get_playback_state, add_note, get_state, rm_note, set_tempo, set_beat, set_record_head,
+            list_midi_inputs, set_clock_source, connect_midi_input, set_recording,
+            list_midi_outputs, set_track_device, set_track_target, set_track_pan,
+            set_track_envelope, set_track_filter, set_track_velocity, set_track_voices,
+            set_track_glide, set_wavetable, set_wavetable_pos, set_mute,
+            set_instrument, set_track_sends, set_fx, set_track_modulation, save_preset,
+            load_preset, save_project, load_project, panic
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
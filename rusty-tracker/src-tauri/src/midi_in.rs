@@ -0,0 +1,125 @@
+use anyhow::{bail, Result};
+use crossbeam::channel::Sender;
+use midir::{Ignore, MidiInput, MidiInputConnection};
+use tracing::*;
+use tracker_lib::{MidiChannel, MidiNote};
+
+/// a midi message received on one of the players midi inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MidiInMsg {
+    NoteOn {
+        channel: MidiChannel,
+        note: MidiNote,
+        vel: u8,
+    },
+    NoteOff {
+        channel: MidiChannel,
+        note: MidiNote,
+    },
+    ControlChange {
+        channel: MidiChannel,
+        control: u8,
+        value: u8,
+    },
+    Clock,
+    Start,
+    Continue,
+    Stop,
+    /// song position in midi beats (16th notes) since the start of the song.
+    SongPosition(u16),
+    Other,
+}
+
+impl From<&[u8]> for MidiInMsg {
+    fn from(bytes: &[u8]) -> Self {
+        match bytes {
+            // a note on with a velocity of zero is a note off
+            [status, note, 0] if status & 0xF0 == 0x90 => MidiInMsg::NoteOff {
+                channel: status & 0x0F,
+                note: *note,
+            },
+            [status, note, vel] if status & 0xF0 == 0x90 => MidiInMsg::NoteOn {
+                channel: status & 0x0F,
+                note: *note,
+                vel: *vel,
+            },
+            [status, note, _] if status & 0xF0 == 0x80 => MidiInMsg::NoteOff {
+                channel: status & 0x0F,
+                note: *note,
+            },
+            [status, control, value] if status & 0xF0 == 0xB0 => MidiInMsg::ControlChange {
+                channel: status & 0x0F,
+                control: *control,
+                value: *value,
+            },
+            [0xF2, lsb, msb] => {
+                MidiInMsg::SongPosition(((*msb as u16 & 0x7F) << 7) | (*lsb as u16 & 0x7F))
+            }
+            [0xF8] => MidiInMsg::Clock,
+            [0xFA] => MidiInMsg::Start,
+            [0xFB] => MidiInMsg::Continue,
+            [0xFC] => MidiInMsg::Stop,
+            _ => MidiInMsg::Other,
+        }
+    }
+}
+
+/// sent from the midir callback thread to the player.
+#[derive(Clone, Debug)]
+pub struct MidiInEvent {
+    /// the name of the input port the message came in on.
+    pub dev: String,
+    /// micro-seconds since an unspecified point, only comparable between events of the same port.
+    pub timestamp: u64,
+    pub msg: MidiInMsg,
+    /// the message as it was received, for the midi monitor.
+    pub bytes: Vec<u8>,
+}
+
+pub fn list_input_ports() -> Result<Vec<String>> {
+    let midi_in = MidiInput::new("rusty-tracker input listing")?;
+
+    Ok(midi_in
+        .ports()
+        .iter()
+        .filter_map(|port| midi_in.port_name(port).ok())
+        .collect())
+}
+
+/// connects to the midi input named `name`. every message received is parsed and sent down `tx`.
+pub fn connect_input(name: &str, tx: Sender<MidiInEvent>) -> Result<MidiInputConnection<()>> {
+    let mut midi_in = MidiInput::new("rusty-tracker input")?;
+    // clock and song position messages are needed to follow an external sequencer.
+    midi_in.ignore(Ignore::Sysex | Ignore::ActiveSense);
+
+    let Some(port) = midi_in
+        .ports()
+        .into_iter()
+        .find(|port| midi_in.port_name(port).is_ok_and(|port_name| port_name == name))
+    else {
+        bail!("there is no midi input named \"{name}\".");
+    };
+
+    let dev = name.to_string();
+
+    match midi_in.connect(
+        &port,
+        name,
+        move |timestamp, bytes, _| {
+            let event = MidiInEvent {
+                dev: dev.clone(),
+                timestamp,
+                msg: MidiInMsg::from(bytes),
+                bytes: bytes.to_vec(),
+            };
+
+            if let Err(e) = tx.send(event) {
+                error!("could not forward midi input to the player. got error: {e}");
+            }
+        },
+        (),
+    ) {
+        Ok(conn) => Ok(conn),
+        Err(e) => bail!("{e}"),
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::{bail, Result};
+use midir::{os::unix::VirtualOutput, MidiOutput, MidiOutputConnection};
+use tracker_lib::{MidiChannel, DEFAULT_MIDI_DEV_NAME};
+
+/// control change number that silences a channel immediately, including release tails.
+pub const ALL_SOUND_OFF: u8 = 120;
+/// control change number that releases every note held on a channel.
+pub const ALL_NOTES_OFF: u8 = 123;
+
+pub fn control_change(channel: MidiChannel, control: u8, value: u8) -> [u8; 3] {
+    [0xB0 | (channel & 0x0F), control & 0x7F, value & 0x7F]
+}
+
+/// checks that `bytes` is exactly one well formed midi message.
+pub fn validate_midi(bytes: &[u8]) -> Result<()> {
+    let Some(status) = bytes.first() else {
+        bail!("an empty midi message can't be sent.");
+    };
+
+    if status & 0x80 == 0 {
+        bail!("midi messages must start with a status byte, got {status:#04X}.");
+    }
+
+    let expected_len = match status {
+        0x80..=0xBF | 0xE0..=0xEF | 0xF2 => 3,
+        0xC0..=0xDF | 0xF1 | 0xF3 => 2,
+        0xF6 | 0xF8..=0xFF => 1,
+        0xF0 => {
+            if bytes.last() != Some(&0xF7) {
+                bail!("system exclusive messages must end with 0xF7.");
+            }
+
+            if let Some(byte) = bytes[1..bytes.len() - 1].iter().find(|byte| **byte & 0x80 != 0) {
+                bail!("system exclusive data bytes must be below 0x80, got {byte:#04X}.");
+            }
+
+            return Ok(());
+        }
+        _ => bail!("{status:#04X} is not a midi status byte that can be sent."),
+    };
+
+    if bytes.len() != expected_len {
+        bail!(
+            "a message with status {status:#04X} must be {expected_len} bytes long, got {}.",
+            bytes.len()
+        );
+    }
+
+    if let Some(byte) = bytes[1..].iter().find(|byte| **byte & 0x80 != 0) {
+        bail!("midi data bytes must be below 0x80, got {byte:#04X}.");
+    }
+
+    Ok(())
+}
+
+/// the channel a message is on, `None` for system messages.
+pub fn message_channel(bytes: &[u8]) -> Option<MidiChannel> {
+    bytes
+        .first()
+        .filter(|status| (0x80..0xF0).contains(*status))
+        .map(|status| status & 0x0F)
+}
+
+pub fn new_midi_dev(name: &str) -> Result<MidiOutputConnection> {
+    let midi_out = MidiOutput::new("midir forwarding output")?;
+
+    // let out_port = select_port(&midi_out, "output")?;
+
+    // let out_port_name = midi_out.port_name(&out_port)?;
+    match midi_out.create_virtual(name) {
+        Ok(dev) => Ok(dev),
+        Err(e) => bail!("{e}"),
+    }
+}
+
+/// lists the midi outputs a track can be assigned to, starting with the players own virtual port.
+pub fn list_output_ports() -> Result<Vec<String>> {
+    let midi_out = MidiOutput::new("rusty-tracker output listing")?;
+
+    Ok([DEFAULT_MIDI_DEV_NAME.to_string()]
+        .into_iter()
+        .chain(
+            midi_out
+                .ports()
+                .iter()
+                .filter_map(|port| midi_out.port_name(port).ok()),
+        )
+        .collect())
+}
+
+/// connects to the (non virtual) midi output named `name`.
+pub fn connect_output(name: &str) -> Result<MidiOutputConnection> {
+    let midi_out = MidiOutput::new("rusty-tracker output")?;
+
+    let Some(port) = midi_out
+        .ports()
+        .into_iter()
+        .find(|port| midi_out.port_name(port).is_ok_and(|port_name| port_name == name))
+    else {
+        bail!("there is no midi output named \"{name}\".");
+    };
+
+    match midi_out.connect(&port, name) {
+        Ok(conn) => Ok(conn),
+        Err(e) => bail!("connecting to midi output \"{name}\" failed. {e}"),
+    }
+}
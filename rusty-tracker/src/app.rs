@@ -5,16 +5,18 @@ use leptos_hotkeys::{provide_hotkeys_context, scopes, use_hotkeys, HotkeysContex
 use leptos_use::{use_element_size, UseElementSizeReturn};
 use sequence::Sequence;
 use serde::{Deserialize, Serialize};
-use serde_wasm_bindgen::to_value;
+use serde_wasm_bindgen::{from_value, to_value};
 use std::{fmt::Display, time::Duration};
 use tauri_sys::event;
 use tracker_lib::{
-    ChannelIndex, Float, MidiNote, MidiNoteCmd, PlaybackCmd, TrackerState, LINE_LEN,
+    ChannelIndex, EventKind, Float, KeyboardEvent, MidiNote, MidiNoteCmd, PlaybackCmd,
+    PlaybackState, StateDelta, TrackerState, LINE_LEN,
 };
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 
 mod header;
+mod midi_monitor;
 pub mod sequence;
 
 pub const TIMEOUT_DURATION: Duration = Duration::from_millis(5);
@@ -133,7 +135,9 @@ pub struct NoteSetStorage {
 
 async fn listen_on_state_change_event(event_writer: WriteSignal<TrackerState>) {
     loop {
-        let mut events = event::listen::<TrackerState>("state-change").await.unwrap();
+        let mut events = event::listen::<TrackerState>(EventKind::State.name())
+            .await
+            .unwrap();
 
         while let Some(event) = events.next().await {
             log!("Received state-change event.");
@@ -142,9 +146,70 @@ async fn listen_on_state_change_event(event_writer: WriteSignal<TrackerState>) {
     }
 }
 
+/// applies an edit to the rows on screen. if an edit was missed they're fetched again.
+fn apply_delta(
+    set_tracker_state: WriteSignal<TrackerState>,
+    start_row: usize,
+    delta: &StateDelta,
+    resync: impl Fn(),
+) {
+    if !set_tracker_state
+        .try_update(|state| state.apply_delta(start_row, delta))
+        .unwrap_or(false)
+    {
+        warn!("missed an edit to the song (revision {}), fetching it again.", delta.revision);
+        resync();
+    }
+}
+
+async fn listen_on_state_delta_event(
+    set_tracker_state: WriteSignal<TrackerState>,
+    start_row: ReadSignal<usize>,
+    resync: impl Fn() + Copy + 'static,
+) {
+    loop {
+        let mut events = event::listen::<StateDelta>(EventKind::Delta.name())
+            .await
+            .unwrap();
+
+        while let Some(event) = events.next().await {
+            log!("Received state-delta event.");
+            apply_delta(
+                set_tracker_state,
+                start_row.get_untracked(),
+                &event.payload,
+                resync,
+            );
+        }
+    }
+}
+
+/// sends an edit to the back end and applies the cells it changed.
+fn edit_song<T: Serialize + 'static>(
+    cmd: &'static str,
+    args: T,
+    set_tracker_state: WriteSignal<TrackerState>,
+    start_row: ReadSignal<usize>,
+    resync: impl Fn() + 'static,
+) {
+    spawn_local(async move {
+        match invoke(cmd, to_value(&args).unwrap()).await {
+            Ok(delta) => match from_value::<StateDelta>(delta) {
+                Ok(delta) => {
+                    apply_delta(set_tracker_state, start_row.get_untracked(), &delta, resync)
+                }
+                Err(e) => error!("{cmd} replied with something that isn't an edit. {e}"),
+            },
+            Err(e) => error!("{cmd} failed. got error: {e:?}."),
+        }
+    });
+}
+
 async fn listen_on_playhead_event(event_writer: WriteSignal<usize>) {
     loop {
-        let mut events = event::listen::<usize>("playhead").await.unwrap();
+        let mut events = event::listen::<usize>(EventKind::Playhead.name())
+            .await
+            .unwrap();
 
         while let Some(event) = events.next().await {
             log!("Received playhead event.");
@@ -153,6 +218,32 @@ async fn listen_on_playhead_event(event_writer: WriteSignal<usize>) {
     }
 }
 
+/// puts the playhead back where the back end has it, e.g. after the ui was reloaded.
+fn restore_playhead(set_playhead: WriteSignal<usize>) {
+    spawn_local(async move {
+        match invoke("get_playback_state", to_value(&()).unwrap()).await {
+            Ok(state) => match from_value::<PlaybackState>(state) {
+                Ok(state) => set_playhead.set(state.row()),
+                Err(e) => error!("could not read the playback state: {e}"),
+            },
+            Err(e) => error!("getting the playback state failed with error: {e:?}"),
+        }
+    });
+}
+
+async fn listen_on_midi_keyboard_event(event_writer: WriteSignal<Option<KeyboardEvent>>) {
+    loop {
+        let mut events = event::listen::<KeyboardEvent>(EventKind::Keyboard.name())
+            .await
+            .unwrap();
+
+        while let Some(event) = events.next().await {
+            log!("Received midi-keyboard event.");
+            event_writer.set(Some(event.payload));
+        }
+    }
+}
+
 #[component]
 pub fn App() -> impl IntoView {
     let (tracker_state, set_tracker_state) = create_signal(TrackerState::empty());
@@ -161,6 +252,13 @@ pub fn App() -> impl IntoView {
     let (start_row, set_start_row) = create_signal(0);
     let (note_storage, set_note_storage) = create_signal::<Option<NoteSetStorage>>(None);
     let (playhead, set_playhead) = create_signal(0);
+    // how many rows the cursor moves after a note is step entered from a midi keyboard.
+    let (step_size, set_step_size) = create_signal(1usize);
+    let fx = create_memo(move |_| tracker_state.get().fx);
+    let (keyboard, set_keyboard) = create_signal::<Option<KeyboardEvent>>(None);
+    // keys held on the midi keyboard, and how many notes of the current chord have been entered.
+    let (held_keys, set_held_keys) = create_signal(0usize);
+    let (chord_size, set_chord_size) = create_signal(0usize);
     let main_el = create_node_ref::<html::Main>();
     let HotkeysContext { .. } = provide_hotkeys_context(main_el, false, scopes!());
 
@@ -195,6 +293,8 @@ pub fn App() -> impl IntoView {
 
     spawn_local(listen_on_state_change_event(set_tracker_state));
     spawn_local(listen_on_playhead_event(set_playhead));
+    restore_playhead(set_playhead);
+    spawn_local(listen_on_midi_keyboard_event(set_keyboard));
 
     create_effect(move |_| {
         let n_lines = num_lines.get();
@@ -241,6 +341,8 @@ pub fn App() -> impl IntoView {
         });
     };
 
+    spawn_local(listen_on_state_delta_event(set_tracker_state, start_row, get_state));
+
     create_effect(move |_| {
         // log!("height: {}", height.get());
         let height = main_w.get();
@@ -417,14 +519,7 @@ pub fn App() -> impl IntoView {
 
             warn!("sending note to backend");
 
-            spawn_local(async move {
-                // warn!("adding note async block");
-                if let Err(e) = invoke("add_note", to_value(&args_play).unwrap()).await {
-                    error!("could not add note. got error: {e:?}.");
-                }
-
-                get_state();
-            });
+            edit_song("add_note", args_play, set_tracker_state, start_row, get_state);
         }
     };
 
@@ -443,17 +538,80 @@ pub fn App() -> impl IntoView {
 
         warn!("removing note on backend");
 
-        spawn_local(async move {
-            // warn!("adding note async block");
-            if let Err(e) = invoke("rm_note", to_value(&args_play).unwrap()).await {
-                error!("could not rm note. got error: {e:?}.");
-            }
-
-            get_state();
-        });
+        edit_song("rm_note", args_play, set_tracker_state, start_row, get_state);
         // }
     };
 
+    // step input: keys pressed together fill successive note columns, the cursor moves on once
+    // they're all released.
+    create_effect(move |_| {
+        let Some(key) = keyboard.get() else {
+            return;
+        };
+
+        if mode.get_untracked() != Mode::Edit {
+            return;
+        }
+
+        let Some(storage) = note_storage.get_untracked() else {
+            return;
+        };
+
+        let step = step_size.get_untracked();
+
+        match key {
+            KeyboardEvent::Pressed((note, vel)) => {
+                set_held_keys.update(|held| *held += 1);
+                let note_number = storage.loc.1 % 6 + chord_size.get_untracked();
+
+                if note_number > 3 {
+                    warn!("no free note column for step entered note {note}");
+                    return;
+                }
+
+                set_chord_size.update(|n| *n += 1);
+                set_note_storage.update(|storage| {
+                    if let Some(storage) = storage {
+                        storage.note = note;
+                    }
+                });
+
+                let args = AddNoteArgs {
+                    note,
+                    vel,
+                    channel: (storage.loc.1 / 6) as ChannelIndex,
+                    start: storage.loc.0,
+                    stop: storage.loc.0 + step,
+                    note_number,
+                };
+
+                edit_song("add_note", args, set_tracker_state, start_row, get_state);
+            }
+            KeyboardEvent::Released(_) => {
+                set_held_keys.update(|held| *held = held.saturating_sub(1));
+
+                if held_keys.get_untracked() == 0 && chord_size.get_untracked() > 0 {
+                    set_chord_size.set(0);
+
+                    let row = storage.loc.0 + step;
+
+                    if row >= start_row.get_untracked() + num_lines.get_untracked() {
+                        set_start_row.set(row);
+                        get_state();
+                    }
+
+                    set_note_storage.update(|storage| {
+                        if let Some(storage) = storage {
+                            storage.loc.0 = row;
+                            storage.n_lines = step as i64;
+                        }
+                    });
+                    set_location.update(|loc| loc.0 = row - start_row.get_untracked());
+                }
+            }
+        }
+    });
+
     let set_display_note = move |note: Option<MidiNoteCmd>| {
         let loc = location.get();
         // let state = tracker_state.get();
@@ -658,6 +816,21 @@ pub fn App() -> impl IntoView {
     //     }
     // });
 
+    // play from the row under the cursor.
+    use_hotkeys!(("keyp") => move |_| {
+        if mode.get() == Mode::Move {
+            let args = PlaybackArgs {
+                playback_cmd: PlaybackCmd::PlayFrom(location.get().0 + start_row.get()),
+            };
+
+            spawn_local(async move {
+                if let Err(e) = invoke("playback", to_value(&args).unwrap()).await {
+                    error!("playing from the cursor failed with error: {e:?}");
+                }
+            });
+        }
+    });
+
     use_hotkeys!(("Escape", "*") => move |_| {
         log!("Escape key has been pressed");
 
@@ -761,7 +934,7 @@ pub fn App() -> impl IntoView {
                 <div class="col-span-3 grid-flow-row p-2">
                     <div class=""> <br/> </div>
                     <div class=""> <br/> </div>
-                    <SideCar set_playhead/>
+                    <SideCar set_playhead step_size set_step_size fx/>
                 </div>
             </div>
         </main>
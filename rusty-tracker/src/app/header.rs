@@ -1,4 +1,4 @@
-use super::sequence::note_to_display;
+use super::{midi_monitor::MidiMonitor, sequence::note_to_display};
 use crate::{
     app::{PlaybackArgs, TIMEOUT_DURATION},
     invoke,
@@ -7,9 +7,13 @@ use async_std::future;
 use futures_util::StreamExt;
 use leptos::{logging::*, *};
 use serde::Serialize;
-use serde_wasm_bindgen::to_value;
+use serde_wasm_bindgen::{from_value, to_value};
 use tauri_sys::event;
-use tracker_lib::{MidiNote, PlaybackCmd};
+use tracker_lib::{
+    ChannelIndex, ClockSource, ClockStatus, EventKind, Float, FmAlgorithm, FmSettings, FxSettings,
+    Instrument, MidiDevices, MidiNote, NoteActivity, PlaybackCmd, SampleMode, SampleZone, Waveform,
+    Wavetable,
+};
 use wasm_bindgen_futures::spawn_local;
 
 #[derive(Serialize)]
@@ -22,6 +26,50 @@ struct BeatArgs {
     beat: u64,
 }
 
+#[derive(Serialize)]
+struct ClockSourceArgs {
+    source: ClockSource,
+}
+
+#[derive(Serialize)]
+struct MidiInputArgs {
+    dev: String,
+}
+
+#[derive(Serialize)]
+struct RecordingArgs {
+    recording: bool,
+}
+
+#[derive(Serialize)]
+struct MuteArgs {
+    track: ChannelIndex,
+    mute: bool,
+}
+
+#[derive(Serialize)]
+struct WavetableArgs {
+    track: ChannelIndex,
+    wavetable: Wavetable,
+}
+
+#[derive(Serialize)]
+struct WavetablePosArgs {
+    track: ChannelIndex,
+    position: Float,
+}
+
+#[derive(Serialize)]
+struct FxArgs {
+    fx: FxSettings,
+}
+
+#[derive(Serialize)]
+struct InstrumentArgs {
+    track: ChannelIndex,
+    instrument: Instrument,
+}
+
 // #[component]
 // pub fn Header() -> impl IntoView {
 //     view! {
@@ -64,21 +112,77 @@ struct BeatArgs {
 // }
 
 #[component]
-pub fn SideCar(set_playhead: WriteSignal<usize>) -> impl IntoView {
+pub fn SideCar(
+    set_playhead: WriteSignal<usize>,
+    step_size: ReadSignal<usize>,
+    set_step_size: WriteSignal<usize>,
+    fx: Memo<FxSettings>,
+) -> impl IntoView {
     view! {
         <h1>"Setttings"</h1>
+        // the last thing that went wrong on the back end
+        <ErrorBar/>
         // playback controls
         <PlaybackControls set_playhead=set_playhead/>
+        // midi keyboard input & recording
+        <RecordControls/>
         // song information (bpm, row_beat)
-        <SettingsMenu/>
+        <SettingsMenu step_size set_step_size/>
+        // internal clock or following an external midi clock
+        <ClockSync/>
         // wave table selection & what note is playing on what track
         <ActivityMonitor/>
+        // send effects & the master limiter of the built in synth
+        <FxControls fx/>
+        // raw midi going in and out
+        <MidiMonitor/>
         // spectrograph
         // <Spectrograph/>
         // waveform analyzer
     }
 }
 
+/// sends a transport command that doesn't need anything done with its result.
+fn send_playback_cmd(playback_cmd: PlaybackCmd) {
+    let args = PlaybackArgs { playback_cmd };
+
+    spawn_local(async move {
+        log!("playback: {playback_cmd:?}");
+        if let Err(e) = invoke("playback", to_value(&args).unwrap()).await {
+            error!("{playback_cmd:?} failed with error: {e:?}");
+        }
+    });
+}
+
+async fn listen_on_backend_error_event(event_writer: WriteSignal<Option<String>>) {
+    loop {
+        let mut events = event::listen::<String>(EventKind::Error.name())
+            .await
+            .unwrap();
+
+        while let Some(event) = events.next().await {
+            error!("back end error: {}", event.payload);
+            event_writer.set(Some(event.payload));
+        }
+    }
+}
+
+#[component]
+fn ErrorBar() -> impl IntoView {
+    let (mesg, set_mesg) = create_signal::<Option<String>>(None);
+
+    spawn_local(listen_on_backend_error_event(set_mesg));
+
+    view! {
+        <Show when=move || mesg.get().is_some()>
+            <div class="justify-center text-center gap-x-2 flex bg-red">
+                <p> { move || mesg.get().unwrap_or_default() } </p>
+                <button class="px-2" on:click=move |_| set_mesg.set(None)> "x" </button>
+            </div>
+        </Show>
+    }
+}
+
 #[component]
 fn PlaybackControls(set_playhead: WriteSignal<usize>) -> impl IntoView {
     view! {
@@ -107,6 +211,12 @@ fn PlaybackControls(set_playhead: WriteSignal<usize>) -> impl IntoView {
             >
                 "start"
             </button>
+            <button class="bg-peach px-2" on:click=move |_| send_playback_cmd(PlaybackCmd::Pause)>
+                "pause"
+            </button>
+            <button class="bg-peach px-2" on:click=move |_| send_playback_cmd(PlaybackCmd::Restart)>
+                "restart"
+            </button>
             // <div class="p-2"></div>
             <button
                 class="bg-peach px-2"
@@ -130,13 +240,116 @@ fn PlaybackControls(set_playhead: WriteSignal<usize>) -> impl IntoView {
             >
                 "stop"
             </button>
+            <button
+                class="bg-red px-2"
+                on:click=move |_| {
+                    spawn_local(async move {
+                        log!("panic");
+                        if let Err(e) = invoke("panic", to_value(&()).unwrap()).await {
+                            error!("sending panic failed with error: {e:?}");
+                        }
+                    });
+                }
+            >
+                "panic"
+            </button>
         </div>
 
     }
 }
 
+/// keeps `event_writer` up to date with the available midi inputs, or outputs if `outputs` is
+/// true, as devices are plugged in and unplugged.
+pub async fn listen_on_midi_devices_event(event_writer: WriteSignal<Vec<String>>, outputs: bool) {
+    loop {
+        let mut events = event::listen::<MidiDevices>(EventKind::Devices.name())
+            .await
+            .unwrap();
+
+        while let Some(event) = events.next().await {
+            log!("Received midi-devices-changed event.");
+            let MidiDevices {
+                inputs,
+                outputs: outs,
+            } = event.payload;
+            event_writer.set(if outputs { outs } else { inputs });
+        }
+    }
+}
+
+/// asks the back end for the names of the available midi inputs.
+fn refresh_midi_inputs(set_inputs: WriteSignal<Vec<String>>) {
+    spawn_local(async move {
+        match invoke("list_midi_inputs", to_value(&()).unwrap()).await {
+            Ok(ports) => set_inputs.set(from_value(ports).unwrap_or_default()),
+            Err(e) => error!("listing midi inputs failed with error: {e:?}"),
+        }
+    });
+}
+
+#[component]
+fn RecordControls() -> impl IntoView {
+    let (inputs, set_inputs) = create_signal::<Vec<String>>(Vec::new());
+    let (recording, set_recording) = create_signal(false);
+
+    let input_change = move |ev| {
+        let args = MidiInputArgs {
+            dev: event_target_value(&ev),
+        };
+
+        if args.dev.is_empty() {
+            return;
+        }
+
+        spawn_local(async move {
+            if let Err(e) = invoke("connect_midi_input", to_value(&args).unwrap()).await {
+                error!("connecting to midi input failed with error: {e:?}");
+            }
+        });
+    };
+
+    let toggle_recording = move |_| {
+        set_recording.update(|rec| *rec = !*rec);
+
+        let args = RecordingArgs {
+            recording: recording.get_untracked(),
+        };
+
+        spawn_local(async move {
+            if let Err(e) = invoke("set_recording", to_value(&args).unwrap()).await {
+                error!("arming recording failed with error: {e:?}");
+            }
+        });
+    };
+
+    refresh_midi_inputs(set_inputs);
+    spawn_local(listen_on_midi_devices_event(set_inputs, false));
+
+    view! {
+        <div class="justify-center text-center gap-x-2 flex">
+            <p> "midi in: " </p>
+            <select on:change=input_change on:focus=move |_| refresh_midi_inputs(set_inputs)>
+                <option value="">"none"</option>
+                <For
+                    each=move || inputs.get()
+                    key=|port| port.clone()
+                    children=move |port| view! {
+                        <option value=port.clone()>{ port.clone() }</option>
+                    }
+                />
+            </select>
+            <button
+                class=move || if recording.get() { "bg-red px-2" } else { "bg-peach px-2" }
+                on:click=toggle_recording
+            >
+                "rec"
+            </button>
+        </div>
+    }
+}
+
 #[component]
-fn SettingsMenu() -> impl IntoView {
+fn SettingsMenu(step_size: ReadSignal<usize>, set_step_size: WriteSignal<usize>) -> impl IntoView {
     let (tempo, set_tempo) = create_signal(110);
     let (beat, set_beat) = create_signal(8);
 
@@ -232,25 +445,120 @@ fn SettingsMenu() -> impl IntoView {
                     <input type="number" name="beat" min=0 max=513 prop:value=beat on:change=row_beat_change/>
                 </div>
             </div>
+            <div class="justify-center text-center">
+                <h1> "Step:" </h1>
+                <input
+                    type="number"
+                    name="step"
+                    min=1
+                    max=64
+                    prop:value=step_size
+                    on:change=move |ev| {
+                        if let Ok(step) = event_target_value(&ev).parse() {
+                            set_step_size.set(step);
+                        }
+                    }
+                />
+            </div>
+        </div>
+    }
+}
+
+async fn listen_on_clock_sync_event(event_writer: WriteSignal<ClockStatus>) {
+    loop {
+        let mut events = event::listen::<ClockStatus>(EventKind::Clock.name())
+            .await
+            .unwrap();
+
+        while let Some(event) = events.next().await {
+            event_writer.set(event.payload);
+        }
+    }
+}
+
+#[component]
+fn ClockSync() -> impl IntoView {
+    let (status, set_status) = create_signal(ClockStatus {
+        source: ClockSource::Internal,
+        bpm: None,
+        running: false,
+    });
+    let (inputs, set_inputs) = create_signal::<Vec<String>>(Vec::new());
+
+    let source_change = move |ev| {
+        let dev = event_target_value(&ev);
+        let args = ClockSourceArgs {
+            source: if dev.is_empty() {
+                ClockSource::Internal
+            } else {
+                ClockSource::External(dev)
+            },
+        };
+
+        spawn_local(async move {
+            if let Err(e) = invoke("set_clock_source", to_value(&args).unwrap()).await {
+                error!("attempt to set the clock source failed with error: {e:?}");
+            }
+        });
+    };
+
+    spawn_local(listen_on_clock_sync_event(set_status));
+    spawn_local(listen_on_midi_devices_event(set_inputs, false));
+    refresh_midi_inputs(set_inputs);
+
+    let status_text = move || {
+        let status = status.get();
+
+        match status.source {
+            ClockSource::Internal => "internal".to_string(),
+            ClockSource::External(_) => format!(
+                "{} | {}",
+                status
+                    .bpm
+                    .map(|bpm| format!("{bpm:.1} bpm"))
+                    .unwrap_or("no clock".into()),
+                if status.running { "running" } else { "stopped" }
+            ),
+        }
+    };
+
+    view! {
+        <div class="justify-center text-center">
+            <h1> "Clock:" </h1>
+            <select on:change=source_change on:focus=move |_| refresh_midi_inputs(set_inputs)>
+                <option value="">"internal"</option>
+                <For
+                    each=move || inputs.get()
+                    key=|port| port.clone()
+                    children=move |port| view! {
+                        <option value=port.clone()>{ port.clone() }</option>
+                    }
+                />
+            </select>
+            <p> { status_text } </p>
         </div>
     }
 }
 
 async fn listen_on_note_change_event(
-    event_writer: WriteSignal<Option<MidiNote>>,
+    event_writer: WriteSignal<[Option<(MidiNote, u8)>; 4]>,
     track_number: usize,
 ) {
     loop {
-        let mut events = event::listen::<(usize, Option<MidiNote>)>("note-change")
+        let mut events = event::listen::<NoteActivity>(EventKind::NoteActivity.name())
             .await
             .unwrap();
 
         while let Some(event) = events.next().await {
-            let (track, midi_note) = event.payload;
+            let NoteActivity {
+                track,
+                column,
+                note,
+            } = event.payload;
 
-            if track == track_number {
-                log!("Received  event.");
-                event_writer.set(midi_note);
+            if track == track_number && column < 4 {
+                log!("Received note-change event.");
+                event_writer.update(|columns| columns[column] = note);
             }
         }
     }
@@ -259,30 +567,367 @@ async fn listen_on_note_change_event(
 #[component]
 fn TrackMonitor(track_number: usize) -> impl IntoView {
     // make signal
-    let (playing_note, set_playing_note) = create_signal(None);
+    let (playing_notes, set_playing_notes) = create_signal([None; 4]);
 
     // start event listener to update signal
-    spawn_local(listen_on_note_change_event(set_playing_note, track_number));
+    spawn_local(listen_on_note_change_event(set_playing_notes, track_number));
 
     view! {
-        { move ||
-            match playing_note.get() {
-                Some(note) => view! {
-                    <div>
-                        <br/>
-                        {note_to_display(note)}
-                        <br/>
-                    </div>
-                },
-                None => view! {
-                    <div>
-                        <br/>
-                        <br/>
-                        <br/>
-                    </div>
+        <div class="grid grid-flow-col">
+            <For
+                each=move || (0..4)
+                key=|column| *column
+                children=move |column| view! {
+                    { move ||
+                        match playing_notes.get()[column] {
+                            Some((note, vel)) => view! {
+                                <div>
+                                    <br/>
+                                    {note_to_display(note)}
+                                    <br/>
+                                    {format!("{vel:02X}")}
+                                </div>
+                            },
+                            None => view! {
+                                <div>
+                                    <br/>
+                                    "---"
+                                    <br/>
+                                    "--"
+                                </div>
+                            },
+                        }
+                    }
+                }
+            />
+        </div>
+    }
+}
+
+#[component]
+fn MuteButton(track_number: usize) -> impl IntoView {
+    let (muted, set_muted) = create_signal(false);
+
+    let toggle = move |_| {
+        set_muted.update(|muted| *muted = !*muted);
+
+        let args = MuteArgs {
+            track: track_number as ChannelIndex,
+            mute: muted.get_untracked(),
+        };
+
+        spawn_local(async move {
+            if let Err(e) = invoke("set_mute", to_value(&args).unwrap()).await {
+                error!("muting track {} failed with error: {e:?}", track_number + 1);
+            }
+        });
+    };
+
+    view! {
+        <button class=move || if muted.get() { "bg-red px-2" } else { "bg-peach px-2" } on:click=toggle>
+            "mute"
+        </button>
+    }
+}
+
+/// the built in shapes offered by the wavetable setter, the empty value is a file.
+const WAVEFORMS: [(&str, Waveform); 4] = [
+    ("sine", Waveform::Sine),
+    ("triangle", Waveform::Triangle),
+    ("saw", Waveform::Saw),
+    ("square", Waveform::Square),
+];
+
+fn send_wavetable(track_number: usize, wavetable: Wavetable) {
+    let args = WavetableArgs {
+        track: track_number as ChannelIndex,
+        wavetable,
+    };
+
+    spawn_local(async move {
+        if let Err(e) = invoke("set_wavetable", to_value(&args).unwrap()).await {
+            error!(
+                "setting the wavetable of track {} failed with error: {e:?}",
+                track_number + 1
+            );
+        }
+    });
+}
+
+#[component]
+fn WavetableSetter(track_number: usize) -> impl IntoView {
+    let (from_file, set_from_file) = create_signal(false);
+    let (path, set_path) = create_signal(String::new());
+    let (frames, set_frames) = create_signal(1_usize);
+
+    let select = move |ev| {
+        let name = event_target_value(&ev);
+
+        match WAVEFORMS.iter().find(|(waveform, _)| *waveform == name) {
+            Some((_, waveform)) => {
+                set_from_file.set(false);
+                send_wavetable(track_number, Wavetable::BuiltIn(*waveform));
+            }
+            None => set_from_file.set(true),
+        }
+    };
+
+    let load = move |_| {
+        let path = path.get_untracked();
+
+        if !path.is_empty() {
+            let frames = frames.get_untracked();
+            send_wavetable(
+                track_number,
+                Wavetable::FromFile {
+                    path: path.into(),
+                    frames,
                 },
+            );
+        }
+    };
+
+    let set_position = move |ev| {
+        let Ok(position) = event_target_value(&ev).parse::<Float>() else {
+            return;
+        };
+
+        let args = WavetablePosArgs {
+            track: track_number as ChannelIndex,
+            position: position / 100.0,
+        };
+
+        spawn_local(async move {
+            if let Err(e) = invoke("set_wavetable_pos", to_value(&args).unwrap()).await {
+                error!(
+                    "moving the wavetable of track {} failed with error: {e:?}",
+                    track_number + 1
+                );
             }
+        });
+    };
+
+    view! {
+        <div class="flex flex-col">
+            <select on:change=select>
+                { WAVEFORMS.map(|(name, _)| view! { <option value=name>{ name }</option> }).collect_view() }
+                <option value="">"file"</option>
+            </select>
+            <div class="flex flex-row" class:hidden=move || !from_file.get()>
+                <input
+                    class="bg-sapphire w-24"
+                    placeholder="table.wav"
+                    on:input=move |ev| set_path.set(event_target_value(&ev))
+                    prop:value=path
+                />
+                <input
+                    type="number"
+                    class="bg-sapphire w-12"
+                    title="frames"
+                    min=1
+                    max=256
+                    prop:value=frames
+                    on:change=move |ev| {
+                        if let Ok(frames) = event_target_value(&ev).parse() {
+                            set_frames.set(frames);
+                        }
+                    }
+                />
+                <button class="bg-peach px-2" on:click=load> "load" </button>
+            </div>
+            <input
+                type="range"
+                min=0
+                max=100
+                value=0
+                class:hidden=move || !from_file.get()
+                on:change=set_position
+            />
+        </div>
+    }
+}
+
+fn send_instrument(track_number: usize, instrument: Instrument) {
+    let args = InstrumentArgs {
+        track: track_number as ChannelIndex,
+        instrument,
+    };
+
+    spawn_local(async move {
+        if let Err(e) = invoke("set_instrument", to_value(&args).unwrap()).await {
+            error!(
+                "setting the instrument of track {} failed with error: {e:?}",
+                track_number + 1
+            );
+        }
+    });
+}
+
+/// the fm algorithms offered by the instrument setter, named by how the operators are wired.
+const FM_ALGORITHMS: [(&str, FmAlgorithm); 8] = [
+    ("4>3>2>1", FmAlgorithm::Stack),
+    ("3+4>2>1", FmAlgorithm::TwoIntoStack),
+    ("3>2+4>1", FmAlgorithm::SplitStack),
+    ("4>3+2>1", FmAlgorithm::BranchStack),
+    ("2>1 4>3", FmAlgorithm::TwoStacks),
+    ("4>1,2,3", FmAlgorithm::OneToThree),
+    ("4>3 1 2", FmAlgorithm::StackAndTwo),
+    ("1 2 3 4", FmAlgorithm::Additive),
+];
+
+/// picks between the oscillator, a sample & fm. the sample is played at its own pitch on middle C
+/// and spread across the whole keyboard.
+#[component]
+fn InstrumentSetter(track_number: usize) -> impl IntoView {
+    let (sampler, set_sampler) = create_signal(false);
+    let (fm, set_fm) = create_signal(false);
+    let (path, set_path) = create_signal(String::new());
+    let (gated, set_gated) = create_signal(false);
+
+    let select = move |ev| {
+        let kind = event_target_value(&ev);
+        set_sampler.set(kind == "sample");
+        set_fm.set(kind == "fm");
+
+        match kind.as_str() {
+            "oscillator" => send_instrument(track_number, Instrument::Oscillator),
+            "fm" => send_instrument(track_number, Instrument::Fm(FmSettings::default())),
+            _ => {}
+        }
+    };
+
+    let select_algorithm = move |ev| {
+        let name = event_target_value(&ev);
+
+        if let Some((_, algorithm)) = FM_ALGORITHMS.iter().find(|(alg, _)| *alg == name) {
+            let fm = FmSettings {
+                algorithm: *algorithm,
+                ..FmSettings::default()
+            };
+
+            send_instrument(track_number, Instrument::Fm(fm));
         }
+    };
+
+    let load = move |_| {
+        let path = path.get_untracked();
+
+        if path.is_empty() {
+            return;
+        }
+
+        let zone = SampleZone {
+            low: 0,
+            high: 127,
+            mode: if gated.get_untracked() {
+                SampleMode::Gated
+            } else {
+                SampleMode::OneShot
+            },
+            ..SampleZone::drum(path, 60)
+        };
+
+        send_instrument(track_number, Instrument::Sampler(vec![zone]));
+    };
+
+    view! {
+        <div class="flex flex-col">
+            <select on:change=select>
+                <option value="oscillator">"oscillator"</option>
+                <option value="sample">"sample"</option>
+                <option value="fm">"fm"</option>
+            </select>
+            <select class:hidden=move || !fm.get() on:change=select_algorithm>
+                { FM_ALGORITHMS.map(|(name, _)| view! { <option value=name>{ name }</option> }).collect_view() }
+            </select>
+            <div class="flex flex-row" class:hidden=move || !sampler.get()>
+                <input
+                    class="bg-sapphire w-24"
+                    placeholder="kick.flac"
+                    on:input=move |ev| set_path.set(event_target_value(&ev))
+                    prop:value=path
+                />
+                <button
+                    class=move || if gated.get() { "bg-maroon px-2" } else { "bg-sapphire px-2" }
+                    on:click=move |_| set_gated.update(|gated| *gated = !*gated)
+                >
+                    { move || if gated.get() { "gated" } else { "one shot" } }
+                </button>
+                <button class="bg-peach px-2" on:click=load> "load" </button>
+            </div>
+        </div>
+    }
+}
+
+/// one slider of the effects panel, `get` & `set` pick the setting it moves.
+#[component]
+fn FxSlider(
+    label: &'static str,
+    fx: Memo<FxSettings>,
+    min: Float,
+    max: Float,
+    get: fn(&FxSettings) -> Float,
+    set: fn(&mut FxSettings, Float),
+) -> impl IntoView {
+    let change = move |ev| {
+        let Ok(value) = event_target_value(&ev).parse::<Float>() else {
+            return;
+        };
+
+        let mut settings = fx.get_untracked();
+        set(&mut settings, value);
+        let args = FxArgs { fx: settings };
+
+        spawn_local(async move {
+            if let Err(e) = invoke("set_fx", to_value(&args).unwrap()).await {
+                error!("setting the {label} failed with error: {e:?}");
+            }
+        });
+    };
+
+    view! {
+        <div class="flex flex-row justify-between gap-x-2">
+            <p> { label } </p>
+            <input
+                type="range"
+                min=min
+                max=max
+                step=(max - min) / 100.0
+                prop:value=move || get(&fx.get())
+                on:change=change
+            />
+        </div>
+    }
+}
+
+#[component]
+fn FxControls(fx: Memo<FxSettings>) -> impl IntoView {
+    view! {
+        <div class="grid grid-flow-col gap-x-2 bg-sapphire">
+            <div class="flex flex-col">
+                <p> "delay" </p>
+                <FxSlider label="beats" fx min=0.125 max=2.0 get={|fx| fx.delay.beats} set={|fx, v| fx.delay.beats = v} />
+                <FxSlider label="feedback" fx min=0.0 max=0.95 get={|fx| fx.delay.feedback} set={|fx, v| fx.delay.feedback = v} />
+                <FxSlider label="level" fx min=0.0 max=1.0 get={|fx| fx.delay.level} set={|fx, v| fx.delay.level = v} />
+            </div>
+            <div class="flex flex-col">
+                <p> "reverb" </p>
+                <FxSlider label="size" fx min=0.0 max=1.0 get={|fx| fx.reverb.size} set={|fx, v| fx.reverb.size = v} />
+                <FxSlider label="damping" fx min=0.0 max=1.0 get={|fx| fx.reverb.damping} set={|fx, v| fx.reverb.damping = v} />
+                <FxSlider label="level" fx min=0.0 max=1.0 get={|fx| fx.reverb.level} set={|fx, v| fx.reverb.level = v} />
+            </div>
+            <div class="flex flex-col">
+                <p> "chorus" </p>
+                <FxSlider label="rate" fx min=0.05 max=5.0 get={|fx| fx.chorus.rate} set={|fx, v| fx.chorus.rate = v} />
+                <FxSlider label="depth" fx min=0.0 max=20.0 get={|fx| fx.chorus.depth} set={|fx, v| fx.chorus.depth = v} />
+                <FxSlider label="level" fx min=0.0 max=1.0 get={|fx| fx.chorus.level} set={|fx, v| fx.chorus.level = v} />
+            </div>
+            <div class="flex flex-col">
+                <p> "master" </p>
+                <FxSlider label="gain" fx min=0.0 max=4.0 get={|fx| fx.master.gain} set={|fx, v| fx.master.gain = v} />
+                <FxSlider label="ceiling" fx min=0.1 max=1.0 get={|fx| fx.master.ceiling} set={|fx, v| fx.master.ceiling = v} />
+            </div>
+        </div>
     }
 }
 
@@ -295,9 +940,9 @@ fn ActivityMonitor() -> impl IntoView {
                 key=move |i| *i
                 children=move |i| view! {
                     <div class="bg-green">
-                        <div>
-                            // TODO: wavetable setter
-                        </div>
+                        <InstrumentSetter track_number=i/>
+                        <WavetableSetter track_number=i/>
+                        <MuteButton track_number=i/>
                         <TrackMonitor track_number=i/>
                     </div>
                 }
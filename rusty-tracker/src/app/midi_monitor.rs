@@ -0,0 +1,175 @@
+use super::header::listen_on_midi_devices_event;
+use crate::invoke;
+use futures_util::StreamExt;
+use leptos::{logging::*, *};
+use serde::Serialize;
+use serde_wasm_bindgen::{from_value, to_value};
+use std::collections::VecDeque;
+use tauri_sys::event;
+use tracker_lib::{EventKind, MidiDirection, MidiMonitorEntry};
+use wasm_bindgen_futures::spawn_local;
+
+/// how many messages the monitor remembers.
+const MONITOR_LEN: usize = 256;
+
+#[derive(Serialize)]
+struct SendMidiArgs {
+    dev: String,
+    midi_cmd: Vec<u8>,
+}
+
+/// parses space separated hex bytes, like "90 3C 7F".
+fn parse_hex_bytes(text: &str) -> Result<Vec<u8>, String> {
+    text.split_whitespace()
+        .map(|byte| {
+            u8::from_str_radix(byte.trim_start_matches("0x"), 16)
+                .map_err(|_| format!("\"{byte}\" is not a hex byte"))
+        })
+        .collect()
+}
+
+/// true for timing messages (clock, active sensing), which flood the monitor.
+fn is_clock(entry: &MidiMonitorEntry) -> bool {
+    matches!(entry.bytes.first(), Some(0xF8 | 0xFE))
+}
+
+async fn listen_on_midi_monitor_event(event_writer: WriteSignal<VecDeque<MidiMonitorEntry>>) {
+    loop {
+        let mut events = event::listen::<MidiMonitorEntry>(EventKind::Monitor.name())
+            .await
+            .unwrap();
+
+        while let Some(event) = events.next().await {
+            event_writer.update(|entries| {
+                if entries.len() >= MONITOR_LEN {
+                    entries.pop_front();
+                }
+
+                entries.push_back(event.payload);
+            });
+        }
+    }
+}
+
+/// asks the back end for the names of the available midi outputs.
+fn refresh_midi_outputs(set_outputs: WriteSignal<Vec<String>>) {
+    spawn_local(async move {
+        match invoke("list_midi_outputs", to_value(&()).unwrap()).await {
+            Ok(ports) => set_outputs.set(from_value(ports).unwrap_or_default()),
+            Err(e) => error!("listing midi outputs failed with error: {e:?}"),
+        }
+    });
+}
+
+#[component]
+pub fn MidiMonitor() -> impl IntoView {
+    let (entries, set_entries) = create_signal(VecDeque::<MidiMonitorEntry>::new());
+    let (hide_clock, set_hide_clock) = create_signal(true);
+    let (outputs, set_outputs) = create_signal::<Vec<String>>(Vec::new());
+    let (dev, set_dev) = create_signal(String::new());
+    let (bytes, set_bytes) = create_signal(String::new());
+    let (send_error, set_send_error) = create_signal::<Option<String>>(None);
+
+    spawn_local(listen_on_midi_monitor_event(set_entries));
+    spawn_local(listen_on_midi_devices_event(set_outputs, true));
+    refresh_midi_outputs(set_outputs);
+
+    let send = move |_| {
+        let midi_cmd = match parse_hex_bytes(&bytes.get_untracked()) {
+            Ok(midi_cmd) => midi_cmd,
+            Err(e) => {
+                set_send_error.set(Some(e));
+                return;
+            }
+        };
+
+        let args = SendMidiArgs {
+            dev: dev.get_untracked(),
+            midi_cmd,
+        };
+
+        if args.dev.is_empty() {
+            set_send_error.set(Some("pick a midi output".into()));
+            return;
+        }
+
+        spawn_local(async move {
+            match invoke("send_midi", to_value(&args).unwrap()).await {
+                Ok(_) => set_send_error.set(None),
+                Err(e) => {
+                    error!("sending raw midi failed with error: {e:?}");
+                    set_send_error.set(e.as_string().or(Some("sending failed".into())));
+                }
+            }
+        });
+    };
+
+    let shown = move || {
+        let hide_clock = hide_clock.get();
+
+        entries
+            .get()
+            .into_iter()
+            .rev()
+            .filter(|entry| !(hide_clock && is_clock(entry)))
+            .take(32)
+            .collect::<Vec<_>>()
+    };
+
+    view! {
+        <div class="justify-center text-center">
+            <h1> "Midi monitor:" </h1>
+            <div class="justify-center gap-x-2 flex">
+                <select
+                    on:change=move |ev| set_dev.set(event_target_value(&ev))
+                    on:focus=move |_| refresh_midi_outputs(set_outputs)
+                >
+                    <option value="">"output"</option>
+                    <For
+                        each=move || outputs.get()
+                        key=|port| port.clone()
+                        children=move |port| view! {
+                            <option value=port.clone()>{ port.clone() }</option>
+                        }
+                    />
+                </select>
+                <input
+                    class="bg-sapphire w-32"
+                    placeholder="90 3C 7F"
+                    on:input=move |ev| set_bytes.set(event_target_value(&ev))
+                    prop:value=bytes
+                />
+                <button class="bg-peach px-2" on:click=send> "send" </button>
+            </div>
+            <p class="text-red"> { move || send_error.get() } </p>
+            <div class="justify-center gap-x-2 flex">
+                <button
+                    class=move || if hide_clock.get() { "bg-maroon px-2" } else { "bg-peach px-2" }
+                    on:click=move |_| set_hide_clock.update(|hide| *hide = !*hide)
+                >
+                    "hide clock"
+                </button>
+                <button class="bg-peach px-2" on:click=move |_| set_entries.update(|entries| entries.clear())>
+                    "clear"
+                </button>
+            </div>
+            <div class="font-mono text-left">
+                { move || shown().into_iter().map(|entry| view! {
+                    <p>
+                        { format!(
+                            "{:>10.3} {} {} {} {}",
+                            entry.timestamp as f64 / 1_000_000.0,
+                            match entry.direction {
+                                MidiDirection::In => "<-",
+                                MidiDirection::Out => "->",
+                            },
+                            entry.dev,
+                            entry.channel.map(|ch| format!("ch{:02}", ch + 1)).unwrap_or("----".into()),
+                            entry.bytes.iter().map(|byte| format!("{byte:02X}")).collect::<Vec<_>>().join(" "),
+                        ) }
+                    </p>
+                }).collect_view() }
+            </div>
+        </div>
+    }
+}
@@ -1,6 +1,38 @@
-use crate::{Mode, NoteSetStorage};
+use super::header::listen_on_midi_devices_event;
+use crate::{invoke, Mode, NoteSetStorage};
 use leptos::{logging::*, *};
-use tracker_lib::{get_cmd_arg_val, Cmd, CmdArg, MidiNote, MidiNoteCmd, RowData, TrackerState};
+use serde::Serialize;
+use serde_wasm_bindgen::{from_value, to_value};
+use tracker_lib::{
+    get_cmd_arg_val, ChannelIndex, Cmd, CmdArg, Float, MidiChannel, MidiNote, MidiNoteCmd,
+    MidiTarget, RowData, Sends, TrackerState,
+};
+use wasm_bindgen_futures::spawn_local;
+
+#[derive(Serialize)]
+struct TrackDeviceArgs {
+    track: ChannelIndex,
+    dev: String,
+    channel: MidiChannel,
+}
+
+#[derive(Serialize)]
+struct TrackTargetArgs {
+    track: ChannelIndex,
+    target: MidiTarget,
+}
+
+#[derive(Serialize)]
+struct TrackPanArgs {
+    track: ChannelIndex,
+    pan: Float,
+}
+
+#[derive(Serialize)]
+struct TrackSendsArgs {
+    track: ChannelIndex,
+    sends: Sends,
+}
 
 #[component]
 pub fn Sequence(
@@ -50,9 +82,27 @@ pub fn Sequence(
             move |_| state.get().sequences[i].channel
         });
 
+        let target = create_memo({
+            let state = state.clone();
+
+            move |_| state.get().sequences[i].target
+        });
+
+        let pan = create_memo({
+            let state = state.clone();
+
+            move |_| state.get().sequences[i].pan
+        });
+
+        let sends = create_memo({
+            let state = state.clone();
+
+            move |_| state.get().sequences[i].sends
+        });
+
         view! {
             <div class="col-span-2 grid-flow-row p-2">
-                <SequenceHeader i=i n_notes=n_notes n_cmds=n_cmds midi_dev=midi_dev midi_chan=midi_chan/>
+                <SequenceHeader i=i n_notes=n_notes n_cmds=n_cmds midi_dev=midi_dev midi_chan=midi_chan target=target pan=pan sends=sends/>
                 <For
                     each=move || row_memo.get()
                     key={
@@ -121,6 +171,14 @@ fn cmd_to_display(cmd: Cmd, arg: Option<CmdArg>) -> String {
     )
 }
 
+/// a recorded control change as its controller & value in hex.
+fn cc_to_display(cc: Option<(u8, u8)>) -> String {
+    match cc {
+        Some((control, value)) => format!("{control:02X}{value:02X}"),
+        None => "----".to_string(),
+    }
+}
+
 #[component]
 pub fn SequenceRow(
     sequence_i: usize,
@@ -167,6 +225,7 @@ pub fn SequenceRow(
                     }
                 }
             />
+            <p> { move || cc_to_display(dat.get().cc) } </p>
         </div>
 
     }
@@ -179,6 +238,9 @@ pub fn SequenceHeader(
     n_cmds: usize,
     midi_dev: Memo<String>,
     midi_chan: Memo<u8>,
+    target: Memo<MidiTarget>,
+    pan: Memo<Float>,
+    sends: Memo<Sends>,
 ) -> impl IntoView {
     let note_headers = (0..n_notes)
         .map(|n| {
@@ -199,18 +261,169 @@ pub fn SequenceHeader(
         })
         .collect_view();
 
+    let (devs, set_devs) = create_signal::<Vec<String>>(Vec::new());
+
+    let refresh_devs = move || {
+        spawn_local(async move {
+            match invoke("list_midi_outputs", to_value(&()).unwrap()).await {
+                Ok(ports) => set_devs.set(from_value(ports).unwrap_or_default()),
+                Err(e) => error!("listing midi outputs failed with error: {e:?}"),
+            }
+        });
+    };
+
+    spawn_local(listen_on_midi_devices_event(set_devs, true));
+
+    let set_device = move |dev: String, channel: MidiChannel| {
+        let args = TrackDeviceArgs {
+            track: i as ChannelIndex,
+            dev,
+            channel,
+        };
+
+        spawn_local(async move {
+            if let Err(e) = invoke("set_track_device", to_value(&args).unwrap()).await {
+                error!("setting the midi device of track {} failed with error: {e:?}", i + 1);
+            }
+        });
+    };
+
+    let set_target = move |target: MidiTarget| {
+        let args = TrackTargetArgs {
+            track: i as ChannelIndex,
+            target,
+        };
+
+        spawn_local(async move {
+            if let Err(e) = invoke("set_track_target", to_value(&args).unwrap()).await {
+                error!("setting the output of track {} failed with error: {e:?}", i + 1);
+            }
+        });
+    };
+
+    // the built in synth is the empty option, no midi port can have an empty name.
+    let output_change = move |ev| {
+        let dev = event_target_value(&ev);
+
+        if dev.is_empty() {
+            set_target(MidiTarget::BuiltinSynth);
+        } else {
+            set_device(dev, midi_chan.get_untracked());
+            set_target(MidiTarget::MidiOut);
+        }
+    };
+
+    let set_pan = move |pan: Float| {
+        let args = TrackPanArgs {
+            track: i as ChannelIndex,
+            pan,
+        };
+
+        spawn_local(async move {
+            if let Err(e) = invoke("set_track_pan", to_value(&args).unwrap()).await {
+                error!("panning track {} failed with error: {e:?}", i + 1);
+            }
+        });
+    };
+
+    // each slider changes one send, `pick` chooses which.
+    let send_slider = move |label: &'static str, pick: fn(&mut Sends) -> &mut Float| {
+        let change = move |ev| {
+            let Ok(level) = event_target_value(&ev).parse::<Float>() else {
+                return;
+            };
+
+            let mut track_sends = sends.get_untracked();
+            *pick(&mut track_sends) = level / 100.0;
+            let args = TrackSendsArgs {
+                track: i as ChannelIndex,
+                sends: track_sends,
+            };
+
+            spawn_local(async move {
+                if let Err(e) = invoke("set_track_sends", to_value(&args).unwrap()).await {
+                    error!("setting the {label} send of track {} failed with error: {e:?}", i + 1);
+                }
+            });
+        };
+
+        view! {
+            <div class="flex flex-row justify-center gap-x-2">
+                <p> { label } </p>
+                <input
+                    type="range"
+                    min=0
+                    max=100
+                    prop:value=move || (*pick(&mut sends.get()) * 100.0).round()
+                    on:change=change
+                />
+            </div>
+        }
+    };
+
+    let is_synth = move || target.get() == MidiTarget::BuiltinSynth;
+
     view! {
         <div class="">
-            <div class="">
-                { move || format!("{}:{}", midi_dev.get(), midi_chan.get() + 1) }
+            <div class="flex flex-row justify-center text-center">
+                <select on:focus=move |_| refresh_devs() on:change=output_change>
+                    <option value="" selected=is_synth>"synth"</option>
+                    <option value=move || midi_dev.get() selected=move || !is_synth()>
+                        { move || midi_dev.get() }
+                    </option>
+                    <For
+                        each=move || devs.get().into_iter().filter(move |dev| *dev != midi_dev.get())
+                        key=|dev| dev.clone()
+                        children=move |dev| view! {
+                            <option value=dev.clone()>{ dev.clone() }</option>
+                        }
+                    />
+                </select>
+                <p> ":" </p>
+                <input
+                    type="number"
+                    min=1
+                    max=16
+                    disabled=is_synth
+                    prop:value=move || midi_chan.get() + 1
+                    on:change=move |ev| {
+                        if let Ok(channel) = event_target_value(&ev).parse::<MidiChannel>() {
+                            if (1..=16).contains(&channel) {
+                                set_device(midi_dev.get_untracked(), channel - 1);
+                            }
+                        }
+                    }
+                />
             </div>
             <div class="">
                 { format!("Track => {}", i + 1) }
             </div>
+            <div class="flex flex-row justify-center gap-x-2" class:hidden=move || !is_synth()>
+                <p> "L" </p>
+                <input
+                    type="range"
+                    min=-100
+                    max=100
+                    prop:value=move || (pan.get() * 100.0).round()
+                    on:change=move |ev| {
+                        if let Ok(pan) = event_target_value(&ev).parse::<Float>() {
+                            set_pan(pan / 100.0);
+                        }
+                    }
+                    on:dblclick=move |_| set_pan(0.0)
+                />
+                <p> "R" </p>
+            </div>
+            <div class:hidden=move || !is_synth()>
+                { send_slider("dly", |sends| &mut sends.delay) }
+                { send_slider("rev", |sends| &mut sends.reverb) }
+                { send_slider("cho", |sends| &mut sends.chorus) }
+            </div>
 
             <div class="grid grid-flow-col">
                 { note_headers }
                 { cmd_headers }
+                <div> "CC" </div>
             </div>
         </div>
     }
@@ -0,0 +1,68 @@
+//! samples per second of the audio path. `per_sample_locked` is how the output used to work, the
+//! synth behind a mutex that's locked for every sample. `block` is the `AudioOutputSync` the app
+//! uses now, it makes a left and a right sample for every frame.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::{
+    hint::black_box,
+    sync::{Arc, Mutex},
+};
+use synth_8080::{Float, SAMPLE_RATE};
+use synth_lib::audio::{AudioOutputSync, TrackerSynth};
+
+/// one second of audio.
+const N_SAMPLES: usize = SAMPLE_RATE as usize;
+
+/// a synth with a chord playing on every track.
+fn playing_synth(n_tracks: usize) -> TrackerSynth {
+    let mut synth = TrackerSynth::new(n_tracks);
+
+    for track in 0..n_tracks {
+        for note in [48_u8, 52, 55] {
+            synth.play(note, 100, track).unwrap();
+        }
+    }
+
+    synth
+}
+
+/// renders `n_frames` one frame at a time, locking the synth for each. it's the same mix as a
+/// block, just one sample long.
+fn per_sample_locked(synth: &Mutex<TrackerSynth>, n_frames: usize) {
+    let mut frame: [Float; 2] = [0.0; 2];
+
+    for _ in 0..n_frames {
+        synth.lock().unwrap().render_block(&mut frame);
+        black_box(frame);
+    }
+}
+
+fn throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("synth throughput");
+    group.throughput(Throughput::Elements(N_SAMPLES as u64));
+    group.sample_size(10);
+
+    for n_tracks in [4, 16] {
+        let synth = Arc::new(Mutex::new(playing_synth(n_tracks)));
+
+        group.bench_with_input(
+            BenchmarkId::new("per_sample_locked", n_tracks),
+            &n_tracks,
+            |b, _| b.iter(|| per_sample_locked(&synth, N_SAMPLES)),
+        );
+
+        let (mut audio, _handle) = AudioOutputSync::new(playing_synth(n_tracks), None);
+
+        group.bench_with_input(BenchmarkId::new("block", n_tracks), &n_tracks, |b, _| {
+            b.iter(|| {
+                for _ in 0..N_SAMPLES * 2 {
+                    black_box(audio.next());
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
@@ -1,12 +1,22 @@
-use super::synth::Synth;
+use super::{
+    fx::{Fx, Mix},
+    osc::OscSource,
+    sampler::Kit,
+    synth::Synth,
+    wavetable::Table,
+};
 use anyhow::{bail, Result};
-use rayon::prelude::*;
+use crossbeam::channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError};
 use rodio::{OutputStream, Source};
-use std::sync::{Arc, Mutex};
+use std::{sync::Arc, thread, time::Duration};
 use synth_8080::{Float, SAMPLE_RATE};
-use synth_8080_lib::{notes::Note, OscType};
 use tracing::*;
-use tracker_lib::ChannelIndex;
+use tracker_lib::{
+    get_cmd_arg_cutoff, get_cmd_arg_glide, get_cmd_arg_pan, get_cmd_arg_val, sweep_cutoff, Adsr,
+    ChannelIndex, Cmd, CmdArg, FilterSettings, FmSettings, FxSettings, GlideSettings, MidiNote,
+    ModMatrix, Sends, VelocitySettings, VoiceSettings, Waveform, CUTOFF_CC, CUTOFF_CMD, GLIDE_CMD,
+    PAN_CMD, RESONANCE_CC, RESONANCE_CMD,
+};
 
 // /// an async struct meant to handle the syntronization of audio sample generation and output. it
 // /// will have the synth synthisize a sample then it will send that sample to the output struct.
@@ -44,26 +54,326 @@ use tracker_lib::ChannelIndex;
 //
 // unsafe impl Send for AudioOutput {}
 
+/// how many samples the audio thread renders at a time.
+pub const BLOCK_SIZE: usize = 256;
+/// how many control changes can wait for the audio thread before new ones are refused.
+const CMD_QUEUE_LEN: usize = 1024;
+/// how many replaced tables, kits & modulation matrices can wait to be freed.
+const GARBAGE_QUEUE_LEN: usize = 64;
+/// how often the garbage collector checks if what it's holding can be freed.
+const GARBAGE_POLL: Duration = Duration::from_millis(100);
+
+/// a wavetable, kit or modulation matrix the audio thread replaced. freeing memory can take a
+/// while, so they're sent off to be dropped on another thread.
+pub enum Garbage {
+    Table(Arc<Table>),
+    Kit(Arc<Kit>),
+    Modulation(Arc<ModMatrix>),
+}
+
+impl Garbage {
+    /// true once nothing but the garbage collector holds it. notes that were playing when a kit
+    /// was replaced keep playing from the old one.
+    fn unused(&self) -> bool {
+        match self {
+            Self::Table(table) => Arc::strong_count(table) == 1,
+            Self::Kit(kit) => Arc::strong_count(kit) == 1,
+            Self::Modulation(modulation) => Arc::strong_count(modulation) == 1,
+        }
+    }
+}
+
+/// frees what the audio thread threw away, holding on to each thing till nothing else uses it.
+/// runs till the synth is dropped.
+fn collect_garbage(garbage: Receiver<Garbage>) {
+    let mut held: Vec<Garbage> = Vec::new();
+
+    loop {
+        match garbage.recv_timeout(GARBAGE_POLL) {
+            Ok(thing) => held.push(thing),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        held.retain(|thing| !thing.unused());
+    }
+}
+
+/// a control change for the synth, applied by the audio thread at the start of the next block.
+#[derive(Clone)]
+pub enum SynthCmd {
+    /// a note, its velocity & the synth to play it on.
+    Play(MidiNote, u8, usize),
+    Stop(MidiNote, usize),
+    SetVolume(Float, Option<ChannelIndex>),
+    SetWaveform(ChannelIndex, Waveform),
+    SetPan(ChannelIndex, Float),
+    SetEnvelope(ChannelIndex, Adsr),
+    SetFilter(ChannelIndex, FilterSettings),
+    SetCutoff(ChannelIndex, Float),
+    SetResonance(ChannelIndex, Float),
+    SetWavetable(ChannelIndex, Arc<Table>),
+    SetWavetablePos(ChannelIndex, Float),
+    /// `None` goes back to the oscillator.
+    SetSampler(ChannelIndex, Option<Arc<Kit>>),
+    /// `None` goes back to the oscillator.
+    SetFm(ChannelIndex, Option<FmSettings>),
+    SetSends(ChannelIndex, Sends),
+    SetFx(FxSettings),
+    /// the tempo, in bpm, that the delay & lfos are synced to.
+    SetTempo(Float),
+    SetVelocity(ChannelIndex, VelocitySettings),
+    SetVoices(ChannelIndex, VoiceSettings),
+    SetGlide(ChannelIndex, GlideSettings),
+    /// in seconds, the glide mode is left be.
+    SetGlideTime(ChannelIndex, Float),
+    /// in an `Arc` so the one it replaces can be freed off the audio thread.
+    SetModulation(ChannelIndex, Arc<ModMatrix>),
+}
+
+impl SynthCmd {
+    /// the synth the command is for, `None` when it's for all of them.
+    fn channel(&self) -> Option<usize> {
+        match *self {
+            Self::Play(_, _, channel) | Self::Stop(_, channel) => Some(channel),
+            Self::SetVolume(_, channel) => channel.map(|channel| channel as usize),
+            Self::SetFx(_) | Self::SetTempo(_) => None,
+            Self::SetWaveform(channel, _)
+            | Self::SetPan(channel, _)
+            | Self::SetEnvelope(channel, _)
+            | Self::SetFilter(channel, _)
+            | Self::SetCutoff(channel, _)
+            | Self::SetResonance(channel, _)
+            | Self::SetWavetable(channel, _)
+            | Self::SetWavetablePos(channel, _)
+            | Self::SetSampler(channel, _)
+            | Self::SetFm(channel, _)
+            | Self::SetSends(channel, _)
+            | Self::SetVelocity(channel, _)
+            | Self::SetVoices(channel, _)
+            | Self::SetGlide(channel, _)
+            | Self::SetGlideTime(channel, _)
+            | Self::SetModulation(channel, _) => Some(channel as usize),
+        }
+    }
+
+    /// what an effect command in a row of `track` does to the synth, if it's one the synth knows.
+    pub fn from_effect(track: ChannelIndex, (cmd, arg): (Cmd, Option<CmdArg>)) -> Option<Self> {
+        let arg = arg?;
+
+        match cmd {
+            PAN_CMD => Some(Self::SetPan(track, get_cmd_arg_pan(arg))),
+            CUTOFF_CMD => Some(Self::SetCutoff(track, get_cmd_arg_cutoff(arg))),
+            RESONANCE_CMD => Some(Self::SetResonance(
+                track,
+                get_cmd_arg_val(arg) as Float / 100.0,
+            )),
+            GLIDE_CMD => Some(Self::SetGlideTime(track, get_cmd_arg_glide(arg))),
+            _ => None,
+        }
+    }
+
+    /// what a midi control change sent to `track` does to the synth, if it's one the synth
+    /// follows.
+    pub fn from_cc(track: ChannelIndex, control: u8, value: u8) -> Option<Self> {
+        let value = value as Float / 127.0;
+
+        match control {
+            CUTOFF_CC => Some(Self::SetCutoff(track, sweep_cutoff(value))),
+            RESONANCE_CC => Some(Self::SetResonance(track, value)),
+            _ => None,
+        }
+    }
+}
+
+/// how the rest of the program talks to the synth that the audio thread owns. sending never
+/// blocks or locks, so it's safe to use from the player's timing loop. cheap to clone.
+#[derive(Clone)]
+pub struct SynthHandle {
+    tx: Sender<SynthCmd>,
+    n_synths: usize,
+}
+
+impl SynthHandle {
+    fn channel_len_check(&self, channel: usize) -> Result<()> {
+        if channel >= self.n_synths {
+            let mesg = format!("the channel {channel} does not exist.");
+            error!(mesg);
+            bail!(mesg);
+        }
+
+        Ok(())
+    }
+
+    /// queues any command for the audio thread. commands for synths that don't exist are refused
+    /// here, so the audio thread never has to report them.
+    pub fn send(&self, cmd: SynthCmd) -> Result<()> {
+        if let Some(channel) = cmd.channel() {
+            self.channel_len_check(channel)?;
+        }
+
+        match self.tx.try_send(cmd) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => bail!("the synth's command queue is full."),
+            Err(TrySendError::Disconnected(_)) => bail!("the audio thread stopped."),
+        }
+    }
+
+    pub fn play(&self, note: MidiNote, velocity: u8, channel: usize) -> Result<()> {
+        self.send(SynthCmd::Play(note, velocity, channel))
+    }
+
+    pub fn stop(&self, note: MidiNote, channel: usize) -> Result<()> {
+        self.send(SynthCmd::Stop(note, channel))
+    }
+
+    pub fn set_volume(&self, volume: Float, channel: Option<ChannelIndex>) -> Result<()> {
+        self.send(SynthCmd::SetVolume(volume, channel))
+    }
+
+    pub fn set_waveform(&self, channel: ChannelIndex, waveform: Waveform) -> Result<()> {
+        self.send(SynthCmd::SetWaveform(channel, waveform))
+    }
+
+    pub fn set_pan(&self, channel: ChannelIndex, pan: Float) -> Result<()> {
+        self.send(SynthCmd::SetPan(channel, pan))
+    }
+
+    pub fn set_envelope(&self, channel: ChannelIndex, adsr: Adsr) -> Result<()> {
+        self.send(SynthCmd::SetEnvelope(channel, adsr))
+    }
+
+    pub fn set_filter(&self, channel: ChannelIndex, filter: FilterSettings) -> Result<()> {
+        self.send(SynthCmd::SetFilter(channel, filter))
+    }
+
+    pub fn set_cutoff(&self, channel: ChannelIndex, cutoff: Float) -> Result<()> {
+        self.send(SynthCmd::SetCutoff(channel, cutoff))
+    }
+
+    pub fn set_resonance(&self, channel: ChannelIndex, resonance: Float) -> Result<()> {
+        self.send(SynthCmd::SetResonance(channel, resonance))
+    }
+
+    /// the table should be loaded before it's sent, so the audio thread never touches the disk.
+    pub fn set_wavetable(&self, channel: ChannelIndex, table: Arc<Table>) -> Result<()> {
+        self.send(SynthCmd::SetWavetable(channel, table))
+    }
+
+    pub fn set_wavetable_pos(&self, channel: ChannelIndex, position: Float) -> Result<()> {
+        self.send(SynthCmd::SetWavetablePos(channel, position))
+    }
+
+    /// like wavetables, the kit should be loaded before it's sent.
+    pub fn set_sampler(&self, channel: ChannelIndex, kit: Option<Arc<Kit>>) -> Result<()> {
+        self.send(SynthCmd::SetSampler(channel, kit))
+    }
+
+    pub fn set_fm(&self, channel: ChannelIndex, fm: Option<FmSettings>) -> Result<()> {
+        self.send(SynthCmd::SetFm(channel, fm))
+    }
+
+    pub fn set_sends(&self, channel: ChannelIndex, sends: Sends) -> Result<()> {
+        self.send(SynthCmd::SetSends(channel, sends))
+    }
+
+    pub fn set_fx(&self, fx: FxSettings) -> Result<()> {
+        self.send(SynthCmd::SetFx(fx))
+    }
+
+    pub fn set_tempo(&self, bpm: Float) -> Result<()> {
+        self.send(SynthCmd::SetTempo(bpm))
+    }
+
+    pub fn set_velocity(&self, channel: ChannelIndex, velocity: VelocitySettings) -> Result<()> {
+        self.send(SynthCmd::SetVelocity(channel, velocity))
+    }
+
+    pub fn set_voices(&self, channel: ChannelIndex, voices: VoiceSettings) -> Result<()> {
+        self.send(SynthCmd::SetVoices(channel, voices))
+    }
+
+    pub fn set_glide(&self, channel: ChannelIndex, glide: GlideSettings) -> Result<()> {
+        self.send(SynthCmd::SetGlide(channel, glide))
+    }
+
+    pub fn set_modulation(&self, channel: ChannelIndex, modulation: ModMatrix) -> Result<()> {
+        self.send(SynthCmd::SetModulation(channel, Arc::new(modulation)))
+    }
+}
+
+/// the rodio source that plays the synth. it owns the `TrackerSynth`, rendering it a block at a
+/// time and applying the commands from its `SynthHandle`s between blocks. samples are interleaved
+/// stereo, left then right.
 pub struct AudioOutputSync {
-    pub synth: Arc<Mutex<TrackerSynth>>,
-    pub _stream: OutputStream,
+    synth: TrackerSynth,
+    cmds: Receiver<SynthCmd>,
+    block: Vec<Float>,
+    /// the next sample of `block` to play.
+    pos: usize,
+    pub _stream: Option<OutputStream>,
+}
+
+impl AudioOutputSync {
+    /// makes the audio source for `synth` and the handle used to control it. `_stream` is kept
+    /// alive with the source, if it's dropped audio output stops.
+    pub fn new(mut synth: TrackerSynth, _stream: Option<OutputStream>) -> (Self, SynthHandle) {
+        let (tx, cmds) = bounded(CMD_QUEUE_LEN);
+        let (trash, garbage) = bounded(GARBAGE_QUEUE_LEN);
+        synth.trash = Some(trash);
+        thread::spawn(move || collect_garbage(garbage));
+
+        let handle = SynthHandle {
+            tx,
+            n_synths: synth.synths.len(),
+        };
+
+        (
+            Self {
+                synth,
+                cmds,
+                block: vec![0.0; BLOCK_SIZE * 2],
+                pos: BLOCK_SIZE * 2,
+                _stream,
+            },
+            handle,
+        )
+    }
+
+    fn next_block(&mut self) {
+        for cmd in self.cmds.try_iter() {
+            if let Err(e) = self.synth.apply(cmd) {
+                error!("{e}");
+            }
+        }
+
+        self.synth.render_block(&mut self.block);
+        self.pos = 0;
+    }
 }
 
 impl Iterator for AudioOutputSync {
     type Item = f32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Some(self.synth.lock().unwrap().get_sample() as f32)
+        if self.pos >= self.block.len() {
+            self.next_block();
+        }
+
+        let sample = self.block[self.pos];
+        self.pos += 1;
+
+        Some(sample as f32)
     }
 }
 
 impl Source for AudioOutputSync {
     fn current_frame_len(&self) -> Option<usize> {
-        None
+        Some(self.block.len() - self.pos)
     }
 
     fn channels(&self) -> u16 {
-        1
+        2
     }
 
     fn sample_rate(&self) -> u32 {
@@ -84,6 +394,12 @@ pub struct TrackerSynth {
     pub synths: Vec<Synth>,
     discount: Float,
     volume: Float,
+    /// the send effects & master chain.
+    fx: Fx,
+    /// where replaced tables, kits & matrices go to be freed, they're dropped in place when `None`.
+    trash: Option<Sender<Garbage>>,
+    /// the left & right gains of each synth at the start and end of the block being rendered.
+    pan_gains: Vec<((Float, Float), (Float, Float))>,
 }
 
 impl Default for TrackerSynth {
@@ -95,7 +411,11 @@ impl Default for TrackerSynth {
 impl TrackerSynth {
     /// n represents how many synths should be started
     pub fn new(n: usize) -> Self {
-        let synths = (0..n).into_iter().map(|i| Synth::new(i)).collect();
+        let synths: Vec<Synth> = (0..n).into_iter().map(|i| Synth::new(i)).collect();
+        let pan_gains = synths
+            .iter()
+            .map(|synth| (synth.pan_gains(), synth.pan_gains()))
+            .collect();
         let discount = 1.0 / (n as Float);
         let volume = 1.0;
 
@@ -103,33 +423,100 @@ impl TrackerSynth {
             synths,
             discount,
             volume,
+            fx: Fx::new(),
+            trash: None,
+            pan_gains,
         }
     }
 
-    pub fn get_sample(&mut self) -> Float {
-        let sample: Float = self
-            .synths
-            .par_iter_mut()
-            .map(|synth| synth.get_sample())
-            .sum();
+    /// fills `out` with the stereo mix of every synth, interleaved left then right. each synth
+    /// renders the whole block into its own buffer before they're panned, summed and run through
+    /// the effects. the synths are rendered one after another, the audio thread never waits on
+    /// another thread.
+    pub fn render_block(&mut self, out: &mut [Float]) {
+        let frames = out.len() / 2;
+
+        self.synths
+            .iter_mut()
+            .for_each(|synth| synth.render_block(frames));
+
+        let gain = self.discount * self.volume;
+        // pans only change between blocks, so each synth's gains are worked out once per block and
+        // slid to from the last block's. a modulated pan moves smoothly instead of stepping.
+        for (gains, synth) in self.pan_gains.iter_mut().zip(self.synths.iter()) {
+            *gains = (gains.1, synth.pan_gains());
+        }
+
+        let step = 1.0 / frames.max(1) as Float;
+
+        for (i, frame) in out.chunks_exact_mut(2).enumerate() {
+            let mut mix = Mix::default();
+            let along = (i + 1) as Float * step;
+
+            for (synth, (from, to)) in self.synths.iter().zip(self.pan_gains.iter()) {
+                let gains = (
+                    from.0 + (to.0 - from.0) * along,
+                    from.1 + (to.1 - from.1) * along,
+                );
 
-        (sample * self.discount * self.volume).tanh()
+                mix.add(synth.block()[i], gains, &synth.sends);
+            }
+
+            (frame[0], frame[1]) = self.fx.process(mix, gain);
+        }
     }
 
+    /// applies a command sent from a `SynthHandle`.
+    pub fn apply(&mut self, cmd: SynthCmd) -> Result<()> {
+        match cmd {
+            SynthCmd::Play(note, velocity, channel) => self.play(note, velocity, channel),
+            SynthCmd::Stop(note, channel) => self.stop(note, channel),
+            SynthCmd::SetVolume(volume, channel) => self.set_volume(volume, channel),
+            SynthCmd::SetWaveform(channel, waveform) => self.set_waveform(channel, waveform),
+            SynthCmd::SetPan(channel, pan) => self.set_pan(channel, pan),
+            SynthCmd::SetEnvelope(channel, adsr) => self.set_envelope(channel, adsr),
+            SynthCmd::SetFilter(channel, filter) => self.set_filter(channel, filter),
+            SynthCmd::SetCutoff(channel, cutoff) => self.set_cutoff(channel, cutoff),
+            SynthCmd::SetResonance(channel, resonance) => self.set_resonance(channel, resonance),
+            SynthCmd::SetWavetable(channel, table) => self.set_wavetable(channel, table),
+            SynthCmd::SetWavetablePos(channel, position) => {
+                self.set_wavetable_pos(channel, position)
+            }
+            SynthCmd::SetSampler(channel, kit) => self.set_sampler(channel, kit),
+            SynthCmd::SetFm(channel, fm) => self.set_fm(channel, fm),
+            SynthCmd::SetSends(channel, sends) => self.set_sends(channel, sends),
+            SynthCmd::SetFx(fx) => {
+                self.set_fx(fx);
+                Ok(())
+            }
+            SynthCmd::SetTempo(bpm) => {
+                self.set_tempo(bpm);
+                Ok(())
+            }
+            SynthCmd::SetVelocity(channel, velocity) => self.set_velocity(channel, velocity),
+            SynthCmd::SetVoices(channel, voices) => self.set_voices(channel, voices),
+            SynthCmd::SetGlide(channel, glide) => self.set_glide(channel, glide),
+            SynthCmd::SetGlideTime(channel, time) => self.set_glide_time(channel, time),
+            SynthCmd::SetModulation(channel, modulation) => {
+                self.set_modulation(channel, modulation)
+            }
+        }
+    }
+
+    /// commands from a `SynthHandle` were checked before they were sent, so this only fails for
+    /// direct callers like the offline renderer.
     fn channel_len_check(&mut self, channel: usize) -> Result<()> {
         if channel >= self.synths.len() {
-            let mesg = format!("the channel {channel} does not exist.");
-            error!(mesg);
-            bail!(mesg);
+            bail!("the channel {channel} does not exist.");
         }
 
         Ok(())
     }
 
-    pub fn play(&mut self, note: Note, channel: usize) -> Result<()> {
+    pub fn play(&mut self, note: MidiNote, velocity: u8, channel: usize) -> Result<()> {
         self.channel_len_check(channel)?;
 
-        if let Err(e) = self.synths[channel].play_note(note) {
+        if let Err(e) = self.synths[channel].play_note(note, velocity) {
             let mesg = format!("playing \"{note}\" on channel {channel}, resulted in error: {e}");
             error!(mesg);
             bail!(mesg);
@@ -138,7 +525,7 @@ impl TrackerSynth {
         Ok(())
     }
 
-    pub fn stop(&mut self, note: Note, channel: usize) -> Result<()> {
+    pub fn stop(&mut self, note: MidiNote, channel: usize) -> Result<()> {
         self.channel_len_check(channel)?;
 
         if let Err(e) = self.synths[channel].stop_note(note) {
@@ -176,11 +563,213 @@ impl TrackerSynth {
         Ok(())
     }
 
-    pub fn set_waveform(&mut self, channel: ChannelIndex, waveform: OscType) -> Result<()> {
+    /// hands `garbage` to the garbage collector. if it can't take any more it's dropped here.
+    fn throw_away(&self, garbage: Garbage) {
+        if let Some(ref trash) = self.trash {
+            if let Err(e) = trash.try_send(garbage) {
+                warn!("couldn't queue garbage, freeing it on the audio thread. {e}");
+                drop(e.into_inner());
+            }
+        }
+    }
+
+    /// throws away the table `source` was playing, if it was one.
+    fn throw_away_source(&self, source: OscSource) {
+        if let OscSource::Table(table) = source {
+            self.throw_away(Garbage::Table(table));
+        }
+    }
+
+    pub fn set_waveform(&mut self, channel: ChannelIndex, waveform: Waveform) -> Result<()> {
         let channel = channel as usize;
 
         self.channel_len_check(channel)?;
-        self.synths[channel].set_waveform(waveform);
+        let old = self.synths[channel].set_waveform(waveform);
+        self.throw_away_source(old);
+
+        Ok(())
+    }
+
+    pub fn set_pan(&mut self, channel: ChannelIndex, pan: Float) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].pan = pan.clamp(-1.0, 1.0);
+
+        Ok(())
+    }
+
+    /// sets the amplitude envelope of `channel`, notes already sounding keep their place in it.
+    pub fn set_envelope(&mut self, channel: ChannelIndex, adsr: Adsr) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].set_envelope(adsr);
+
+        Ok(())
+    }
+
+    pub fn set_filter(&mut self, channel: ChannelIndex, filter: FilterSettings) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].set_filter(filter);
+
+        Ok(())
+    }
+
+    /// moves the cutoff of `channel`'s filter, leaving the rest of it be.
+    pub fn set_cutoff(&mut self, channel: ChannelIndex, cutoff: Float) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        let mut filter = self.synths[channel].filter();
+        filter.cutoff = cutoff;
+        self.synths[channel].set_filter(filter);
+
+        Ok(())
+    }
+
+    pub fn set_resonance(&mut self, channel: ChannelIndex, resonance: Float) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        let mut filter = self.synths[channel].filter();
+        filter.resonance = resonance.clamp(0.0, 1.0);
+        self.synths[channel].set_filter(filter);
+
+        Ok(())
+    }
+
+    pub fn set_wavetable(&mut self, channel: ChannelIndex, table: Arc<Table>) -> Result<()> {
+        let channel = channel as usize;
+
+        if let Err(e) = self.channel_len_check(channel) {
+            self.throw_away(Garbage::Table(table));
+            return Err(e);
+        }
+
+        let old = self.synths[channel].set_wavetable(table);
+        self.throw_away_source(old);
+
+        Ok(())
+    }
+
+    pub fn set_wavetable_pos(&mut self, channel: ChannelIndex, position: Float) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].set_wavetable_pos(position);
+
+        Ok(())
+    }
+
+    pub fn set_sampler(&mut self, channel: ChannelIndex, kit: Option<Arc<Kit>>) -> Result<()> {
+        let channel = channel as usize;
+
+        if let Err(e) = self.channel_len_check(channel) {
+            if let Some(kit) = kit {
+                self.throw_away(Garbage::Kit(kit));
+            }
+
+            return Err(e);
+        }
+
+        if let Some(old) = self.synths[channel].set_sampler(kit) {
+            self.throw_away(Garbage::Kit(old));
+        }
+
+        Ok(())
+    }
+
+    pub fn set_fm(&mut self, channel: ChannelIndex, fm: Option<FmSettings>) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].set_fm(fm);
+
+        Ok(())
+    }
+
+    pub fn set_sends(&mut self, channel: ChannelIndex, sends: Sends) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].sends = sends;
+
+        Ok(())
+    }
+
+    pub fn set_fx(&mut self, fx: FxSettings) {
+        self.fx.set_settings(fx);
+    }
+
+    /// the master limiter is on unless this turns it off, see `Fx::set_limiting`.
+    pub fn set_limiting(&mut self, limiting: bool) {
+        self.fx.set_limiting(limiting);
+    }
+
+    pub fn set_tempo(&mut self, bpm: Float) {
+        self.fx.set_tempo(bpm);
+        self.synths
+            .iter_mut()
+            .for_each(|synth| synth.set_tempo(bpm));
+    }
+
+    pub fn set_velocity(
+        &mut self,
+        channel: ChannelIndex,
+        velocity: VelocitySettings,
+    ) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].set_velocity(velocity);
+
+        Ok(())
+    }
+
+    pub fn set_voices(&mut self, channel: ChannelIndex, voices: VoiceSettings) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].set_voices(voices);
+
+        Ok(())
+    }
+
+    pub fn set_glide(&mut self, channel: ChannelIndex, glide: GlideSettings) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].set_glide(glide);
+
+        Ok(())
+    }
+
+    pub fn set_glide_time(&mut self, channel: ChannelIndex, time: Float) -> Result<()> {
+        let channel = channel as usize;
+
+        self.channel_len_check(channel)?;
+        self.synths[channel].set_glide_time(time);
+
+        Ok(())
+    }
+
+    pub fn set_modulation(
+        &mut self,
+        channel: ChannelIndex,
+        modulation: Arc<ModMatrix>,
+    ) -> Result<()> {
+        let channel = channel as usize;
+
+        if let Err(e) = self.channel_len_check(channel) {
+            self.throw_away(Garbage::Modulation(modulation));
+            return Err(e);
+        }
+
+        let old = self.synths[channel].set_modulation(modulation);
+        self.throw_away(Garbage::Modulation(old));
 
         Ok(())
     }
@@ -0,0 +1,72 @@
+//! bounces a song to a wav with the built in synth, no audio device needed.
+//!
+//! usage: render-song <song.json> <out.wav> [--rate HZ] [--bits 16|24|32] [--tempo BPM] [--beat N]
+//!        [--tail SECONDS] [--stems] [--mute TRACK]...
+use anyhow::{bail, Context, Result};
+use std::{env, fs};
+use synth_lib::{
+    render::{render_wav, BitDepth, RenderSettings},
+    start_logging,
+};
+use tracing::*;
+use tracker_lib::TrackerState;
+
+fn parse_args(args: &[String]) -> Result<(String, String, RenderSettings)> {
+    let mut settings = RenderSettings::default();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("{arg} needs a value"));
+
+        match arg.as_str() {
+            "--rate" => settings.sample_rate = value()?.parse()?,
+            "--bits" => {
+                settings.bit_depth = match value()?.as_str() {
+                    "16" => BitDepth::Int16,
+                    "24" => BitDepth::Int24,
+                    "32" => BitDepth::Float32,
+                    bits => bail!("unsupported bit depth {bits}, use 16, 24 or 32."),
+                }
+            }
+            "--tempo" => settings.tempo = value()?.parse()?,
+            "--beat" => settings.beat = value()?.parse()?,
+            "--tail" => settings.tail = value()?.parse()?,
+            "--stems" => settings.stems = true,
+            "--mute" => {
+                let track: usize = value()?.parse()?;
+
+                if track == 0 {
+                    bail!("tracks are counted from 1.");
+                }
+
+                settings.muted.push(track - 1);
+            }
+            _ => paths.push(arg.clone()),
+        }
+    }
+
+    let [song, out] = paths.as_slice() else {
+        bail!("usage: render-song <song.json> <out.wav> [--rate HZ] [--bits 16|24|32] [--tempo BPM] [--beat N] [--tail SECONDS] [--stems] [--mute TRACK]...");
+    };
+
+    Ok((song.clone(), out.clone(), settings))
+}
+
+fn main() -> Result<()> {
+    start_logging()?;
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (song_path, out, settings) = parse_args(&args)?;
+
+    let song: TrackerState = serde_json::from_str(
+        &fs::read_to_string(&song_path).with_context(|| format!("reading {song_path}"))?,
+    )
+    .with_context(|| format!("{song_path} is not a song"))?;
+
+    for file in render_wav(&song, &settings, out)? {
+        info!("wrote {}", file.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use synth_8080::{Float, SAMPLE_RATE};
+use tracker_lib::Adsr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Done,
+}
+
+/// how much a level changes per sample to cover `distance` in `secs`.
+fn step(distance: Float, secs: Float) -> Float {
+    if secs <= 0.0 {
+        Float::INFINITY
+    } else {
+        distance / (secs * SAMPLE_RATE as Float)
+    }
+}
+
+/// the running state of an `Adsr` for one note. it starts in the attack stage, `release` is called
+/// on note off and it's done once the release reaches silence.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    stage: Stage,
+    level: Float,
+    /// per sample fall of the release, worked out from the level it was released at.
+    release_step: Float,
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Envelope {
+    pub fn new() -> Self {
+        Self {
+            stage: Stage::Attack,
+            level: 0.0,
+            release_step: 0.0,
+        }
+    }
+
+    /// the level for the next sample.
+    pub fn next(&mut self, adsr: &Adsr) -> Float {
+        let sustain = adsr.sustain.clamp(0.0, 1.0);
+
+        match self.stage {
+            Stage::Attack => {
+                self.level += step(1.0, adsr.attack);
+
+                if self.level >= 1.0 {
+                    self.level = 1.0;
+                    self.stage = Stage::Decay;
+                }
+            }
+            Stage::Decay => {
+                self.level -= step(1.0 - sustain, adsr.decay);
+
+                if self.level <= sustain {
+                    self.level = sustain;
+                    self.stage = Stage::Sustain;
+                }
+            }
+            Stage::Sustain => self.level = sustain,
+            Stage::Release => {
+                self.level -= self.release_step;
+
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    self.stage = Stage::Done;
+                }
+            }
+            Stage::Done => self.level = 0.0,
+        }
+
+        self.level
+    }
+
+    /// starts the release from wherever the envelope is.
+    pub fn release(&mut self, adsr: &Adsr) {
+        if self.stage != Stage::Done {
+            self.release_step = step(self.level, adsr.release);
+            self.stage = Stage::Release;
+        }
+    }
+
+    /// goes back to the attack from wherever the level is, so a retriggered note doesn't click.
+    pub fn retrigger(&mut self) {
+        self.stage = Stage::Attack;
+    }
+
+    /// true once the release has faded out, the note can be dropped.
+    pub fn is_done(&self) -> bool {
+        self.stage == Stage::Done
+    }
+
+    pub fn level(&self) -> Float {
+        self.level
+    }
+}
@@ -0,0 +1,50 @@
+use synth_8080::{Float, SAMPLE_RATE};
+use tracker_lib::FilterMode;
+
+/// the lowest & highest cutoff the filter is run at, the top is kept under nyquist.
+const MIN_CUTOFF: Float = 20.0;
+const MAX_CUTOFF: Float = SAMPLE_RATE as Float * 0.45;
+
+/// a state variable filter, the trapezoidal kind, so it stays stable while the cutoff is swept.
+#[derive(Clone, Debug, Default)]
+pub struct Svf {
+    ic1eq: Float,
+    ic2eq: Float,
+}
+
+impl Svf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// filters one sample. `resonance` goes from 0.0 to 1.0.
+    pub fn process(
+        &mut self,
+        input: Float,
+        mode: FilterMode,
+        cutoff: Float,
+        resonance: Float,
+    ) -> Float {
+        let cutoff = cutoff.clamp(MIN_CUTOFF, MAX_CUTOFF);
+        let g = (std::f64::consts::PI as Float * cutoff / SAMPLE_RATE as Float).tan();
+        // damping, resonance is kept just shy of self oscillation.
+        let k = 2.0 - 1.98 * resonance.clamp(0.0, 1.0);
+
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+
+        let v3 = input - self.ic2eq;
+        let v1 = a1 * self.ic1eq + a2 * v3;
+        let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        match mode {
+            FilterMode::LowPass => v2,
+            FilterMode::BandPass => v1,
+            FilterMode::HighPass => input - k * v1 - v2,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use super::envelope::Envelope;
+use synth_8080::{Float, SAMPLE_RATE};
+use tracker_lib::{FmAlgorithm, FmSettings, N_OPERATORS};
+
+/// the operators that modulate each operator, and the operators that are carriers, as bit masks
+/// where bit 0 is operator 1. modulators always have a higher number than what they modulate, so
+/// the operators can be run from 4 down to 1.
+fn routing(algorithm: FmAlgorithm) -> ([u8; N_OPERATORS], u8) {
+    match algorithm {
+        FmAlgorithm::Stack => ([0b0010, 0b0100, 0b1000, 0], 0b0001),
+        FmAlgorithm::TwoIntoStack => ([0b0010, 0b1100, 0, 0], 0b0001),
+        FmAlgorithm::SplitStack => ([0b1010, 0b0100, 0, 0], 0b0001),
+        FmAlgorithm::BranchStack => ([0b0110, 0, 0b1000, 0], 0b0001),
+        FmAlgorithm::TwoStacks => ([0b0010, 0, 0b1000, 0], 0b0101),
+        FmAlgorithm::OneToThree => ([0b1000, 0b1000, 0b1000, 0], 0b0111),
+        FmAlgorithm::StackAndTwo => ([0, 0, 0b1000, 0], 0b0111),
+        FmAlgorithm::Additive => ([0; N_OPERATORS], 0b1111),
+    }
+}
+
+/// the operators of one fm note.
+#[derive(Clone, Debug, Default)]
+pub struct FmVoice {
+    /// how far through its cycle each operator is, 0.0 to 1.0.
+    phases: [Float; N_OPERATORS],
+    envs: [Envelope; N_OPERATORS],
+    /// set once the operator envelopes have been released.
+    released: bool,
+    /// the last two samples of operator 4, averaged for the feedback so it doesn't squeal.
+    feedback: [Float; 2],
+}
+
+impl FmVoice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the operator envelopes attack again from wherever they are.
+    pub fn retrigger(&mut self) {
+        self.envs.iter_mut().for_each(Envelope::retrigger);
+        self.released = false;
+    }
+
+    /// the next sample at `freq`. the operator envelopes are released once `gate` goes false.
+    /// carriers are averaged so switching algorithms doesn't jump the volume.
+    pub fn next(&mut self, settings: &FmSettings, freq: Float, gate: bool) -> Float {
+        if !gate && !self.released {
+            self.envs
+                .iter_mut()
+                .zip(settings.operators.iter())
+                .for_each(|(env, operator)| env.release(&operator.envelope));
+            self.released = true;
+        }
+
+        let (modulators, carriers) = routing(settings.algorithm);
+        let mut out: [Float; N_OPERATORS] = [0.0; N_OPERATORS];
+
+        for op in (0..N_OPERATORS).rev() {
+            let operator = &settings.operators[op];
+            let mut modulation: Float = (0..N_OPERATORS)
+                .filter(|from| modulators[op] & (1 << from) != 0)
+                .map(|from| out[from])
+                .sum();
+
+            if op == N_OPERATORS - 1 {
+                let last = (self.feedback[0] + self.feedback[1]) / 2.0;
+                modulation += last * settings.feedback.clamp(0.0, 1.0);
+            }
+
+            let sine = ((self.phases[op] + modulation) * std::f64::consts::TAU as Float).sin();
+            let level = self.envs[op].next(&operator.envelope);
+            out[op] = sine * level * operator.level;
+
+            if op == N_OPERATORS - 1 {
+                self.feedback = [self.feedback[1], sine * level];
+            }
+
+            let step = freq * operator.ratio.max(0.0) / SAMPLE_RATE as Float;
+            self.phases[op] = (self.phases[op] + step).fract();
+        }
+
+        let heard: Float = (0..N_OPERATORS)
+            .filter(|op| carriers & (1 << op) != 0)
+            .map(|op| out[op])
+            .sum();
+
+        heard / carriers.count_ones() as Float
+    }
+}
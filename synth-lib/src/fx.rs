@@ -0,0 +1,376 @@
+use synth_8080::{Float, SAMPLE_RATE};
+use tracker_lib::{
+    ChorusSettings, DelaySettings, FxSettings, MasterSettings, ReverbSettings, Sends,
+};
+
+/// a stereo sample, (left, right).
+pub type Frame = (Float, Float);
+
+/// the longest echo in seconds, longer synced times are cut short.
+const MAX_DELAY: Float = 4.0;
+/// the chorus voices sit this many milliseconds behind the dry signal, plus the depth.
+const CHORUS_BASE: Float = 7.0;
+const CHORUS_MAX: Float = 50.0;
+/// how quickly the limiter lets go after a peak, in seconds.
+const LIMITER_RELEASE: Float = 0.1;
+/// the freeverb comb & allpass lengths, tuned for 44.1 kHz. the right side is a bit longer so
+/// the two sides don't ring in step.
+const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+const STEREO_SPREAD: usize = 23;
+const REVERB_INPUT_GAIN: Float = 0.015;
+
+fn samples(secs: Float) -> Float {
+    secs * SAMPLE_RATE as Float
+}
+
+/// scales a delay length tuned at 44.1 kHz to the sample rate.
+fn tuned(len: usize) -> usize {
+    (len as u64 * SAMPLE_RATE as u64 / 44_100).max(1) as usize
+}
+
+/// the panned sample of every track, summed into the dry mix and each effect's input.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Mix {
+    pub dry: Frame,
+    pub delay: Frame,
+    pub reverb: Frame,
+    pub chorus: Frame,
+}
+
+impl Mix {
+    /// adds one track's `sample`, panned by `gains`.
+    pub fn add(&mut self, sample: Float, (left, right): Frame, sends: &Sends) {
+        let (l, r) = (sample * left, sample * right);
+        let add = |bus: &mut Frame, amount: Float| {
+            bus.0 += l * amount;
+            bus.1 += r * amount;
+        };
+
+        add(&mut self.dry, 1.0);
+        add(&mut self.delay, sends.delay);
+        add(&mut self.reverb, sends.reverb);
+        add(&mut self.chorus, sends.chorus);
+    }
+}
+
+/// a ring buffer that can be read at a fractional number of samples ago.
+#[derive(Clone, Debug)]
+struct DelayLine {
+    buf: Vec<Float>,
+    pos: usize,
+}
+
+impl DelayLine {
+    fn new(len: usize) -> Self {
+        Self {
+            buf: vec![0.0; len.max(2)],
+            pos: 0,
+        }
+    }
+
+    /// the sample from `delay` samples ago, 1.0 is the last one written.
+    fn read(&self, delay: Float) -> Float {
+        let len = self.buf.len();
+        let delay = delay.clamp(1.0, (len - 1) as Float);
+        let whole = delay as usize;
+        let frac = delay - whole as Float;
+        let a = self.buf[(self.pos + len - whole) % len];
+        let b = self.buf[(self.pos + len - whole - 1) % len];
+
+        a + (b - a) * frac
+    }
+
+    fn write(&mut self, sample: Float) {
+        self.buf[self.pos] = sample;
+        self.pos = (self.pos + 1) % self.buf.len();
+    }
+}
+
+/// a stereo echo, the feedback is softened a little each time around.
+#[derive(Clone, Debug)]
+struct Delay {
+    lines: [DelayLine; 2],
+    /// one pole low pass state of each side's feedback.
+    damp: Frame,
+}
+
+impl Delay {
+    fn new() -> Self {
+        let len = samples(MAX_DELAY) as usize + 2;
+
+        Self {
+            lines: [DelayLine::new(len), DelayLine::new(len)],
+            damp: (0.0, 0.0),
+        }
+    }
+
+    fn process(&mut self, (left, right): Frame, settings: &DelaySettings, bpm: Float) -> Frame {
+        let secs = settings.beats.max(0.0) * 60.0 / bpm.max(1.0);
+        let delay = samples(secs.min(MAX_DELAY));
+        let feedback = settings.feedback.clamp(0.0, 0.95);
+
+        let out = (self.lines[0].read(delay), self.lines[1].read(delay));
+        self.damp.0 += (out.0 - self.damp.0) * 0.5;
+        self.damp.1 += (out.1 - self.damp.1) * 0.5;
+        self.lines[0].write(left + self.damp.0 * feedback);
+        self.lines[1].write(right + self.damp.1 * feedback);
+
+        out
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Comb {
+    buf: Vec<Float>,
+    pos: usize,
+    store: Float,
+}
+
+impl Comb {
+    fn new(len: usize) -> Self {
+        Self {
+            buf: vec![0.0; len],
+            pos: 0,
+            store: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: Float, feedback: Float, damping: Float) -> Float {
+        let out = self.buf[self.pos];
+        self.store = out * (1.0 - damping) + self.store * damping;
+        self.buf[self.pos] = input + self.store * feedback;
+        self.pos = (self.pos + 1) % self.buf.len();
+
+        out
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Allpass {
+    buf: Vec<Float>,
+    pos: usize,
+}
+
+impl Allpass {
+    fn new(len: usize) -> Self {
+        Self {
+            buf: vec![0.0; len],
+            pos: 0,
+        }
+    }
+
+    fn process(&mut self, input: Float) -> Float {
+        let buffered = self.buf[self.pos];
+        self.buf[self.pos] = input + buffered * 0.5;
+        self.pos = (self.pos + 1) % self.buf.len();
+
+        buffered - input
+    }
+}
+
+/// one side of the reverb, parallel combs into a chain of allpasses.
+#[derive(Clone, Debug)]
+struct ReverbSide {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl ReverbSide {
+    fn new(spread: usize) -> Self {
+        Self {
+            combs: COMB_TUNING
+                .iter()
+                .map(|len| Comb::new(tuned(len + spread)))
+                .collect(),
+            allpasses: ALLPASS_TUNING
+                .iter()
+                .map(|len| Allpass::new(tuned(len + spread)))
+                .collect(),
+        }
+    }
+
+    fn process(&mut self, input: Float, feedback: Float, damping: Float) -> Float {
+        let wet: Float = self
+            .combs
+            .iter_mut()
+            .map(|comb| comb.process(input, feedback, damping))
+            .sum();
+
+        self.allpasses
+            .iter_mut()
+            .fold(wet, |sample, allpass| allpass.process(sample))
+    }
+}
+
+/// a freeverb style room.
+#[derive(Clone, Debug)]
+struct Reverb {
+    sides: [ReverbSide; 2],
+}
+
+impl Reverb {
+    fn new() -> Self {
+        Self {
+            sides: [ReverbSide::new(0), ReverbSide::new(STEREO_SPREAD)],
+        }
+    }
+
+    fn process(&mut self, (left, right): Frame, settings: &ReverbSettings) -> Frame {
+        let input = (left + right) * REVERB_INPUT_GAIN;
+        let feedback = 0.7 + 0.28 * settings.size.clamp(0.0, 1.0);
+        let damping = settings.damping.clamp(0.0, 1.0) * 0.4;
+
+        (
+            self.sides[0].process(input, feedback, damping),
+            self.sides[1].process(input, feedback, damping),
+        )
+    }
+}
+
+/// a voice on each side that drifts behind the input, a quarter cycle apart.
+#[derive(Clone, Debug)]
+struct Chorus {
+    lines: [DelayLine; 2],
+    /// 0.0 to 1.0 through the lfo's cycle.
+    phase: Float,
+}
+
+impl Chorus {
+    fn new() -> Self {
+        let len = samples(CHORUS_MAX / 1000.0) as usize + 2;
+
+        Self {
+            lines: [DelayLine::new(len), DelayLine::new(len)],
+            phase: 0.0,
+        }
+    }
+
+    fn process(&mut self, (left, right): Frame, settings: &ChorusSettings) -> Frame {
+        let depth = settings.depth.clamp(0.0, CHORUS_MAX - CHORUS_BASE);
+        let delay = |phase: Float| {
+            let lfo = 0.5 + 0.5 * (phase * std::f64::consts::TAU as Float).sin();
+
+            samples((CHORUS_BASE + depth * lfo) / 1000.0)
+        };
+
+        self.lines[0].write(left);
+        self.lines[1].write(right);
+
+        let out = (
+            self.lines[0].read(delay(self.phase)),
+            self.lines[1].read(delay(self.phase + 0.25)),
+        );
+        self.phase = (self.phase + settings.rate.max(0.0) / SAMPLE_RATE as Float) % 1.0;
+
+        out
+    }
+}
+
+/// a peak limiter. it clamps down on a peak straight away and lets go over `LIMITER_RELEASE`.
+#[derive(Clone, Debug)]
+struct Limiter {
+    gain: Float,
+    release: Float,
+}
+
+impl Limiter {
+    fn new() -> Self {
+        Self {
+            gain: 1.0,
+            release: 1.0 - (-1.0 / samples(LIMITER_RELEASE)).exp(),
+        }
+    }
+
+    fn process(&mut self, (left, right): Frame, settings: &MasterSettings) -> Frame {
+        let ceiling = settings.ceiling.clamp(0.0, 1.0);
+        let (left, right) = (left * settings.gain, right * settings.gain);
+        let peak = left.abs().max(right.abs());
+        let target = if peak > ceiling { ceiling / peak } else { 1.0 };
+
+        if target < self.gain {
+            self.gain = target;
+        } else {
+            self.gain += (target - self.gain) * self.release;
+        }
+
+        (
+            (left * self.gain).clamp(-ceiling, ceiling),
+            (right * self.gain).clamp(-ceiling, ceiling),
+        )
+    }
+}
+
+/// the send effects & master chain of the `TrackerSynth`.
+#[derive(Clone, Debug)]
+pub struct Fx {
+    settings: FxSettings,
+    /// the tempo the delay is synced to.
+    bpm: Float,
+    delay: Delay,
+    reverb: Reverb,
+    chorus: Chorus,
+    limiter: Limiter,
+    /// false skips the limiter, see `set_limiting`.
+    limiting: bool,
+}
+
+impl Default for Fx {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fx {
+    pub fn new() -> Self {
+        Self {
+            settings: FxSettings::default(),
+            bpm: 110.0,
+            delay: Delay::new(),
+            reverb: Reverb::new(),
+            chorus: Chorus::new(),
+            limiter: Limiter::new(),
+            limiting: true,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: FxSettings) {
+        self.settings = settings;
+    }
+
+    pub fn set_tempo(&mut self, bpm: Float) {
+        self.bpm = bpm;
+    }
+
+    /// with the limiter off everything is linear, so mixes of a few tracks each add up to the
+    /// mix of them all, as it was before the limiter.
+    pub fn set_limiting(&mut self, limiting: bool) {
+        self.limiting = limiting;
+    }
+
+    /// runs the sends through their effects and mixes them back in with the dry signal. `gain` is
+    /// applied to everything going in, the output has been through the limiter (unless it's off).
+    pub fn process(&mut self, mix: Mix, gain: Float) -> Frame {
+        let scale = |(left, right): Frame| (left * gain, right * gain);
+        let FxSettings {
+            delay,
+            reverb,
+            chorus,
+            master,
+        } = self.settings;
+
+        let echo = self.delay.process(scale(mix.delay), &delay, self.bpm);
+        let room = self.reverb.process(scale(mix.reverb), &reverb);
+        let wide = self.chorus.process(scale(mix.chorus), &chorus);
+        let dry = scale(mix.dry);
+
+        let left = dry.0 + echo.0 * delay.level + room.0 * reverb.level + wide.0 * chorus.level;
+        let right = dry.1 + echo.1 * delay.level + room.1 * reverb.level + wide.1 * chorus.level;
+
+        if self.limiting {
+            self.limiter.process((left, right), &master)
+        } else {
+            (left * master.gain, right * master.gain)
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use synth_8080::{Float, SAMPLE_RATE};
+use tracker_lib::{LfoRate, LfoSettings, LfoShape};
+
+/// a low frequency oscillator, -1.0 to 1.0. it runs free for the whole track, it isn't restarted by
+/// notes.
+#[derive(Clone, Debug)]
+pub struct Lfo {
+    /// 0.0 to 1.0 through the cycle.
+    phase: Float,
+    /// the sample & hold level for this cycle.
+    held: Float,
+    /// xorshift state for sample & hold, seeded so renders come out the same every time.
+    rng: u32,
+}
+
+impl Lfo {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            phase: 0.0,
+            held: 0.0,
+            rng: seed.max(1),
+        }
+    }
+
+    fn random(&mut self) -> Float {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+
+        self.rng as Float / u32::MAX as Float * 2.0 - 1.0
+    }
+
+    /// cycles per second, tempo synced rates are worked out from `bpm`.
+    fn freq(rate: LfoRate, bpm: Float) -> Float {
+        match rate {
+            LfoRate::Hz(hz) => hz.max(0.0),
+            LfoRate::Beats(beats) if beats > 0.0 => bpm / 60.0 / beats,
+            LfoRate::Beats(_) => 0.0,
+        }
+    }
+
+    pub fn next(&mut self, settings: &LfoSettings, bpm: Float) -> Float {
+        let phase = self.phase;
+
+        let value = match settings.shape {
+            LfoShape::Sine => (phase * std::f64::consts::TAU as Float).sin(),
+            LfoShape::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::Saw => 2.0 * phase - 1.0,
+            LfoShape::SampleHold => self.held,
+        };
+
+        self.phase += Self::freq(settings.rate, bpm) / SAMPLE_RATE as Float;
+
+        if self.phase >= 1.0 {
+            self.phase %= 1.0;
+            self.held = self.random();
+        }
+
+        value
+    }
+}
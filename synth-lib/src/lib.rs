@@ -1,28 +1,43 @@
 #![feature(trivial_bounds)]
-use anyhow::Result;
-use audio::{AudioOutputSync, TrackerSynth};
+use anyhow::{bail, Result};
+use audio::{AudioOutputSync, SynthHandle, TrackerSynth};
 use rodio::{OutputStream, OutputStreamHandle, Source};
-use std::sync::{Arc, Mutex};
 pub use synth_8080::start_logging;
 pub use synth_8080_lib::notes::Note;
 use tracing::*;
 
 pub mod audio;
+pub mod envelope;
+pub mod filter;
+pub mod fm;
+pub mod fx;
+pub mod lfo;
+pub mod osc;
+pub mod preset;
+pub mod render;
+pub mod sampler;
 pub mod synth;
+pub mod wavetable;
 
-pub fn init_synth() -> Result<(
-    Arc<Mutex<TrackerSynth>>,
+/// makes a synth with `n` voices and opens the default audio output for it. the synth lives in the
+/// returned source, it's played through the handle. logging is left to the caller, `start_logging`
+/// can be used if nothing else sets it up.
+pub fn init_synth(
+    n: usize,
+) -> Result<(
+    SynthHandle,
     OutputStreamHandle,
     impl Source<Item = f32> + Iterator<Item = f32>,
 )> {
-    start_logging()?;
     info!("initializing synth");
 
     // let (sample_dest, sample_rx) = unbounded();
     // let (sync_tx, sync) = unbounded();
 
-    let synth = Arc::new(Mutex::new(TrackerSynth::default()));
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
+    let (_stream, stream_handle) = match OutputStream::try_default() {
+        Ok(output) => output,
+        Err(e) => bail!("could not open an audio output. {e}"),
+    };
 
     // let a_io = audio::AudioOutput {
     //     synth,
@@ -32,10 +47,7 @@ pub fn init_synth() -> Result<(
     // };
     //
     // let audio = Audio::new(sync_tx, sample_rx);
-    let audio = AudioOutputSync {
-        synth: synth.clone(),
-        _stream,
-    };
+    let (audio, synth) = AudioOutputSync::new(TrackerSynth::new(n), Some(_stream));
 
     Ok((synth, stream_handle, audio))
 }
@@ -0,0 +1,73 @@
+use super::wavetable::Table;
+use std::sync::Arc;
+use synth_8080::{Float, SAMPLE_RATE};
+use tracker_lib::{MidiNote, Waveform};
+
+/// the frequency of a midi note, A4 (69) is 440 Hz.
+pub fn note_freq(note: MidiNote) -> Float {
+    440.0 * (2.0 as Float).powf((note as Float - 69.0) / 12.0)
+}
+
+/// softens the jump of a saw or square wave at `phase`, so they don't alias as badly.
+fn poly_blep(phase: Float, step: Float) -> Float {
+    if phase < step {
+        let t = phase / step;
+
+        t + t - t * t - 1.0
+    } else if phase > 1.0 - step {
+        let t = (phase - 1.0) / step;
+
+        t * t + t + t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// what an oscillator plays, one of the built in shapes or a wavetable loaded from a file.
+#[derive(Clone, Debug)]
+pub enum OscSource {
+    Basic(Waveform),
+    Table(Arc<Table>),
+}
+
+impl Default for OscSource {
+    fn default() -> Self {
+        Self::Basic(Waveform::default())
+    }
+}
+
+/// a single oscillator. the frequency is passed in every sample so it can be slid or modulated.
+#[derive(Clone, Debug, Default)]
+pub struct Osc {
+    /// how far through the cycle it is, 0.0 to 1.0.
+    phase: Float,
+}
+
+impl Osc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the next sample of `source`. `position` picks where in a wavetable to read from, the
+    /// built in shapes ignore it.
+    pub fn next(&mut self, source: &OscSource, position: Float, freq: Float) -> Float {
+        let step = (freq / SAMPLE_RATE as Float).clamp(0.0, 0.5);
+        let phase = self.phase;
+
+        let sample = match source {
+            OscSource::Basic(Waveform::Sine) => (phase * std::f64::consts::TAU as Float).sin(),
+            OscSource::Basic(Waveform::Triangle) => 1.0 - 4.0 * (phase - 0.5).abs(),
+            OscSource::Basic(Waveform::Saw) => 2.0 * phase - 1.0 - poly_blep(phase, step),
+            OscSource::Basic(Waveform::Square) => {
+                let square = if phase < 0.5 { 1.0 } else { -1.0 };
+
+                square + poly_blep(phase, step) - poly_blep((phase + 0.5) % 1.0, step)
+            }
+            OscSource::Table(table) => table.sample(phase, position),
+        };
+
+        self.phase = (phase + step) % 1.0;
+
+        sample
+    }
+}
@@ -0,0 +1,50 @@
+use super::{audio::SynthCmd, sampler::Kit, wavetable::Table};
+use anyhow::{Context, Result};
+use std::{fs, path::Path, sync::Arc};
+use tracker_lib::{ChannelIndex, Instrument, InstrumentPreset, Wavetable};
+
+/// the commands that give `track` the sound of `preset`. its wavetable & samples are loaded from
+/// disk here, so this should never be called on the audio thread.
+pub fn preset_cmds(track: ChannelIndex, preset: &InstrumentPreset) -> Result<Vec<SynthCmd>> {
+    let source = match preset.wavetable {
+        Wavetable::BuiltIn(waveform) => SynthCmd::SetWaveform(track, waveform),
+        Wavetable::FromFile { ref path, frames } => {
+            SynthCmd::SetWavetable(track, Arc::new(Table::load(path, frames)?))
+        }
+    };
+
+    let (kit, fm) = match preset.instrument {
+        Instrument::Oscillator => (None, None),
+        Instrument::Sampler(ref zones) => (Some(Arc::new(Kit::load(zones)?)), None),
+        Instrument::Fm(fm) => (None, Some(fm)),
+    };
+
+    Ok(vec![
+        source,
+        SynthCmd::SetWavetablePos(track, preset.wavetable_pos),
+        SynthCmd::SetSampler(track, kit),
+        SynthCmd::SetFm(track, fm),
+        SynthCmd::SetEnvelope(track, preset.envelope),
+        SynthCmd::SetFilter(track, preset.filter),
+        SynthCmd::SetVelocity(track, preset.velocity),
+        SynthCmd::SetVoices(track, preset.voices),
+        SynthCmd::SetGlide(track, preset.glide),
+        SynthCmd::SetModulation(track, Arc::new(preset.modulation.clone())),
+    ])
+}
+
+pub fn save_preset(preset: &InstrumentPreset, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+
+    fs::write(path, serde_json::to_string_pretty(preset)?)
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+pub fn load_preset(path: impl AsRef<Path>) -> Result<InstrumentPreset> {
+    let path = path.as_ref();
+
+    serde_json::from_str(
+        &fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?,
+    )
+    .with_context(|| format!("{} is not an instrument preset", path.display()))
+}
@@ -0,0 +1,416 @@
+use super::{
+    audio::{SynthCmd, TrackerSynth, BLOCK_SIZE},
+    preset::preset_cmds,
+};
+use anyhow::{bail, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rayon::prelude::*;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use synth_8080::{Float, SAMPLE_RATE};
+use tracing::*;
+use tracker_lib::{
+    timeline::{row_nanos, RowEvent, Timeline},
+    ChannelIndex, MidiNoteCmd, MidiTarget, TrackerState,
+};
+
+/// how the samples of a rendered wav are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitDepth {
+    Int16,
+    #[default]
+    Int24,
+    Float32,
+}
+
+impl BitDepth {
+    fn spec(self, sample_rate: u32) -> WavSpec {
+        let (bits_per_sample, sample_format) = match self {
+            Self::Int16 => (16, SampleFormat::Int),
+            Self::Int24 => (24, SampleFormat::Int),
+            Self::Float32 => (32, SampleFormat::Float),
+        };
+
+        WavSpec {
+            channels: 2,
+            sample_rate,
+            bits_per_sample,
+            sample_format,
+        }
+    }
+}
+
+/// what a song is rendered to, and how it's timed.
+#[derive(Clone, Debug)]
+pub struct RenderSettings {
+    /// the sample rate of the wav, the synth is resampled to it if they differ.
+    pub sample_rate: u32,
+    pub bit_depth: BitDepth,
+    /// same as the players tempo & beat, a row lasts 1/`beat` of a beat at `tempo` bpm.
+    pub tempo: u64,
+    pub beat: u64,
+    /// seconds rendered after the last row so released notes can ring out.
+    pub tail: Float,
+    /// also writes every track to its own wav next to the mix. stems skip the master limiter so
+    /// they add up to the mix as it was before the limiter, they can go over full scale.
+    pub stems: bool,
+    /// tracks left out, like muting them in the player.
+    pub muted: Vec<usize>,
+    /// the rows to render, from the first row to the last one with anything on it when `None`.
+    pub rows: Option<Range<usize>>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            sample_rate: SAMPLE_RATE,
+            bit_depth: BitDepth::default(),
+            tempo: 110,
+            beat: 8,
+            tail: 1.0,
+            stems: false,
+            muted: Vec::new(),
+            rows: None,
+        }
+    }
+}
+
+/// one past the last row that has a note, command or control change on any track.
+fn song_end(song: &TrackerState) -> usize {
+    song.sequences
+        .iter()
+        .filter_map(|sequence| {
+            sequence.data.iter().rposition(|row| {
+                row.notes.iter().any(Option::is_some)
+                    || row.cmds.iter().any(Option::is_some)
+                    || row.cc.is_some()
+            })
+        })
+        .max()
+        .map(|row| row + 1)
+        .unwrap_or(0)
+}
+
+/// the sample, at `sample_rate`, that row `row` (counted from the first rendered row) starts on.
+/// rows are timed the same as the player times them.
+fn row_start(row: usize, sample_rate: u32, settings: &RenderSettings) -> usize {
+    let nanos = row as u128 * row_nanos(settings.tempo, settings.beat) as u128;
+
+    (nanos * sample_rate as u128 / 1_000_000_000) as usize
+}
+
+/// extends the interleaved stereo `samples` to `frames` frames long, a block at a time.
+fn render_until(synth: &mut TrackerSynth, samples: &mut Vec<Float>, frames: usize) {
+    let start = samples.len();
+
+    if frames * 2 > start {
+        samples.resize(frames * 2, 0.0);
+        samples[start..]
+            .chunks_mut(BLOCK_SIZE * 2)
+            .for_each(|block| synth.render_block(block));
+    }
+}
+
+/// plays what the timeline says a row does on `synth`. tracks that play to a midi output are
+/// skipped, like the player skips them.
+fn play_events(synth: &mut TrackerSynth, song: &TrackerState, events: Vec<RowEvent>) -> Result<()> {
+    let builtin = |track: usize| song.sequences[track].target == MidiTarget::BuiltinSynth;
+
+    for event in events {
+        match event {
+            RowEvent::Note { track, note, .. } if builtin(track) => match note {
+                MidiNoteCmd::PlayNote((midi_note, vel)) => synth.play(midi_note, vel, track)?,
+                MidiNoteCmd::StopNote(midi_note) => synth.stop(midi_note, track)?,
+                MidiNoteCmd::HoldNote => {}
+            },
+            RowEvent::Cc {
+                track,
+                control,
+                value,
+            } if builtin(track) => {
+                if let Some(cmd) = SynthCmd::from_cc(track as ChannelIndex, control, value) {
+                    synth.apply(cmd)?;
+                }
+            }
+            RowEvent::Cmd { track, cmd } if builtin(track) => {
+                if let Some(cmd) = SynthCmd::from_effect(track as ChannelIndex, cmd) {
+                    synth.apply(cmd)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// plays `song` through its own `TrackerSynth` as fast as it can be synthesized, stepping through
+/// it with the same `Timeline` as the player. only `solo` is heard when it's set, and without the
+/// master limiter. the samples are interleaved stereo at the synths sample rate.
+fn render_tracks(
+    song: &TrackerState,
+    settings: &RenderSettings,
+    rows: &Range<usize>,
+    solo: Option<usize>,
+) -> Result<Vec<Float>> {
+    let n_tracks = song.sequences.len();
+    let mut synth = TrackerSynth::new(n_tracks);
+    let mut timeline = Timeline::default();
+    let tail = (settings.tail.max(0.0) * SAMPLE_RATE as Float) as usize;
+    let mut samples = Vec::with_capacity((row_start(rows.len(), SAMPLE_RATE, settings) + tail) * 2);
+    let muted =
+        |track: usize| settings.muted.contains(&track) || solo.is_some_and(|solo| solo != track);
+
+    for (track, sequence) in song.sequences.iter().enumerate() {
+        synth.set_pan(track as ChannelIndex, sequence.pan)?;
+        synth.set_sends(track as ChannelIndex, sequence.sends)?;
+
+        for cmd in preset_cmds(track as ChannelIndex, &sequence.preset)? {
+            synth.apply(cmd)?;
+        }
+    }
+
+    synth.set_fx(song.fx);
+    synth.set_tempo(settings.tempo as Float);
+    synth.set_limiting(solo.is_none());
+    timeline.seek(rows.start);
+
+    for (i, row) in rows.clone().enumerate() {
+        let events = timeline.step(song, row, muted);
+        play_events(&mut synth, song, events)?;
+
+        render_until(
+            &mut synth,
+            &mut samples,
+            row_start(i + 1, SAMPLE_RATE, settings),
+        );
+    }
+
+    play_events(&mut synth, song, timeline.release())?;
+
+    let end = samples.len() / 2 + tail;
+    render_until(&mut synth, &mut samples, end);
+
+    Ok(samples)
+}
+
+/// linear interpolation of interleaved stereo from the synths sample rate to `sample_rate`.
+fn resample(samples: Vec<Float>, sample_rate: u32) -> Vec<Float> {
+    if sample_rate == SAMPLE_RATE || samples.len() < 2 {
+        return samples;
+    }
+
+    let step = SAMPLE_RATE as Float / sample_rate as Float;
+    let frames = samples.len() / 2;
+    let len = (frames as Float / step) as usize;
+
+    (0..len)
+        .flat_map(|i| {
+            let pos = i as Float * step;
+            let i = pos as usize;
+            let frac = pos - i as Float;
+            let next = (i + 1).min(frames - 1);
+
+            [0, 1].map(|side| {
+                let now = samples[i * 2 + side];
+
+                now + (samples[next * 2 + side] - now) * frac
+            })
+        })
+        .collect()
+}
+
+fn write_wav(path: &Path, samples: &[Float], settings: &RenderSettings) -> Result<()> {
+    let mut writer = WavWriter::create(path, settings.bit_depth.spec(settings.sample_rate))?;
+
+    for sample in samples.iter().copied() {
+        // int wavs can't go over full scale, float ones keep stems that do whole.
+        let clipped = sample.clamp(-1.0, 1.0);
+
+        match settings.bit_depth {
+            BitDepth::Int16 => writer.write_sample((clipped * i16::MAX as Float) as i16)?,
+            BitDepth::Int24 => writer.write_sample((clipped * 8_388_607.0) as i32)?,
+            BitDepth::Float32 => writer.write_sample(sample as f32)?,
+        }
+    }
+
+    writer.finalize()?;
+
+    Ok(())
+}
+
+/// where the stem of `track` goes, "song.wav" -> "song-track-1.wav".
+fn stem_path(path: &Path, track: usize) -> PathBuf {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or("render".into());
+
+    path.with_file_name(format!("{name}-track-{}.wav", track + 1))
+}
+
+/// renders `song` through the built in synth to a wav at `path`, without an audio device. tracks
+/// that target a midi output can't be heard and are left out. returns the files that were
+/// written, the mix first then the stems.
+pub fn render_wav(
+    song: &TrackerState,
+    settings: &RenderSettings,
+    path: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+
+    if settings.tempo == 0 || settings.beat == 0 {
+        bail!("can't render with a tempo or beat of zero.");
+    }
+
+    if settings.sample_rate == 0 {
+        bail!("can't render at a sample rate of zero.");
+    }
+
+    let rows = settings.rows.clone().unwrap_or(0..song_end(song));
+
+    song.sequences
+        .iter()
+        .enumerate()
+        .filter(|(_, sequence)| sequence.target == MidiTarget::MidiOut)
+        .for_each(|(i, _)| {
+            warn!(
+                "track {} plays to a midi output, it won't be rendered.",
+                i + 1
+            )
+        });
+
+    info!(
+        "rendering rows {:0X}..{:0X} to {}",
+        rows.start,
+        rows.end,
+        path.display()
+    );
+
+    let mix = resample(
+        render_tracks(song, settings, &rows, None)?,
+        settings.sample_rate,
+    );
+    write_wav(path, &mix, settings)?;
+
+    let mut written = vec![path.to_path_buf()];
+
+    if settings.stems {
+        let stems: Result<Vec<PathBuf>> = (0..song.sequences.len())
+            .into_par_iter()
+            .filter(|track| song.sequences[*track].target == MidiTarget::BuiltinSynth)
+            .filter(|track| !settings.muted.contains(track))
+            .map(|track| {
+                let stem = resample(
+                    render_tracks(song, settings, &rows, Some(track))?,
+                    settings.sample_rate,
+                );
+                let stem_path = stem_path(path, track);
+                write_wav(&stem_path, &stem, settings)?;
+
+                Ok(stem_path)
+            })
+            .collect();
+
+        written.extend(stems?);
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracker_lib::{RowData, CUTOFF_CC};
+
+    fn one_note_song() -> TrackerState {
+        let mut song = TrackerState::empty();
+        song.add_note(Some(MidiNoteCmd::PlayNote((60, 100))), 0, 0, 0)
+            .unwrap();
+
+        song
+    }
+
+    #[test]
+    fn a_song_renders_its_rows_and_the_tail() {
+        let song = one_note_song();
+        let settings = RenderSettings::default();
+        let rows = 0..song_end(&song);
+        let samples = render_tracks(&song, &settings, &rows, None).unwrap();
+        let tail = (settings.tail * SAMPLE_RATE as Float) as usize;
+
+        assert_eq!(rows, 0..1);
+        assert_eq!(
+            samples.len(),
+            (row_start(1, SAMPLE_RATE, &settings) + tail) * 2
+        );
+        assert!(samples.iter().any(|sample| *sample != 0.0));
+    }
+
+    #[test]
+    fn a_cc_lane_counts_towards_the_song_length() {
+        let mut song = one_note_song();
+        song.sequences
+            .iter_mut()
+            .for_each(|sequence| sequence.data.resize(4, RowData::default()));
+        song.set_cc(1, 2, Some((CUTOFF_CC, 0))).unwrap();
+
+        assert_eq!(song_end(&song), 3);
+        assert!(render_tracks(&song, &RenderSettings::default(), &(0..3), None).is_ok());
+    }
+
+    #[test]
+    fn muted_tracks_are_silent() {
+        let song = one_note_song();
+        let settings = RenderSettings {
+            muted: vec![0],
+            ..RenderSettings::default()
+        };
+        let samples = render_tracks(&song, &settings, &(0..1), None).unwrap();
+
+        assert!(samples.iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn empty_tracks_are_skipped() {
+        let mut song = one_note_song();
+        song.sequences[1].data.clear();
+
+        assert!(render_tracks(&song, &RenderSettings::default(), &(0..1), None).is_ok());
+    }
+
+    #[test]
+    fn resampling_to_the_same_rate_changes_nothing() {
+        let samples = vec![0.0, 0.5, 1.0, -1.0];
+
+        assert_eq!(resample(samples.clone(), SAMPLE_RATE), samples);
+    }
+
+    #[test]
+    fn resampling_interpolates_between_frames() {
+        // a ramp on the left, silence on the right.
+        let samples: Vec<Float> = (0..8).flat_map(|i| [i as Float, 0.0]).collect();
+        let resampled = resample(samples, SAMPLE_RATE / 2);
+
+        assert_eq!(resampled.len(), 8);
+        assert_eq!(resampled[2], 2.0);
+        assert_eq!(resampled[3], 0.0);
+
+        let resampled = resample(vec![0.0, 0.0, 1.0, 1.0], SAMPLE_RATE * 2);
+        assert_eq!(resampled[2], 0.5);
+    }
+
+    #[test]
+    fn stems_sit_next_to_the_mix() {
+        assert_eq!(
+            stem_path(Path::new("out/song.wav"), 0),
+            Path::new("out/song-track-1.wav")
+        );
+        assert_eq!(
+            stem_path(Path::new("song"), 3),
+            Path::new("song-track-4.wav")
+        );
+    }
+}
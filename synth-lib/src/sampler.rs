@@ -0,0 +1,306 @@
+use anyhow::{bail, Context, Result};
+use rodio::{Decoder, Source};
+use std::{fs::File, io::BufReader, path::Path, sync::Arc};
+use synth_8080::{Float, SAMPLE_RATE};
+use tracing::*;
+use tracker_lib::{MidiNote, SampleMode, SampleZone};
+
+/// a decoded sample, mixed down to mono.
+#[derive(Clone, Debug)]
+pub struct SampleData {
+    samples: Vec<Float>,
+    sample_rate: u32,
+}
+
+impl SampleData {
+    /// decodes a wav or flac file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        let decoder = Decoder::new(BufReader::new(file))
+            .with_context(|| format!("decoding {}", path.display()))?;
+        let channels = decoder.channels().max(1) as usize;
+        let sample_rate = decoder.sample_rate();
+
+        let interleaved: Vec<i16> = decoder.collect();
+        let samples: Vec<Float> = interleaved
+            .chunks(channels)
+            .map(|frame| {
+                frame.iter().map(|sample| *sample as Float).sum::<Float>()
+                    / (channels as Float * i16::MAX as Float)
+            })
+            .collect();
+
+        if samples.is_empty() {
+            bail!("{} has no audio in it.", path.display());
+        }
+
+        info!(
+            "loaded {} frames at {sample_rate} Hz from {}",
+            samples.len(),
+            path.display()
+        );
+
+        Ok(Self {
+            samples,
+            sample_rate,
+        })
+    }
+}
+
+/// a `SampleZone` with its sample loaded.
+#[derive(Clone, Debug)]
+pub struct Zone {
+    data: Arc<SampleData>,
+    root: MidiNote,
+    low: MidiNote,
+    high: MidiNote,
+    mode: SampleMode,
+    loop_points: Option<(usize, usize)>,
+}
+
+impl Zone {
+    /// `zone` played from `data`. loop points past the end of the sample are pulled in to it, a
+    /// loop that's empty once they are is refused.
+    fn new(data: Arc<SampleData>, zone: &SampleZone) -> Result<Self> {
+        let frames = data.samples.len();
+        let loop_points = match zone.loop_points {
+            Some((start, end)) => {
+                let (start, end) = (start.min(frames - 1), end.min(frames));
+
+                if start >= end {
+                    bail!(
+                        "the loop of {} from frame {start} to {end} is empty.",
+                        zone.path.display()
+                    );
+                }
+
+                Some((start, end))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            data,
+            root: zone.root,
+            low: zone.low.min(zone.high),
+            high: zone.high.max(zone.low),
+            mode: zone.mode,
+            loop_points,
+        })
+    }
+}
+
+/// every zone of a sampler instrument, loaded and ready to play.
+#[derive(Clone, Debug, Default)]
+pub struct Kit {
+    zones: Vec<Zone>,
+}
+
+impl Kit {
+    /// loads the sample of every zone. zones that share a file share the loaded sample.
+    pub fn load(zones: &[SampleZone]) -> Result<Self> {
+        let mut loaded: Vec<(&Path, Arc<SampleData>)> = Vec::new();
+        let mut kit = Vec::with_capacity(zones.len());
+
+        for zone in zones {
+            let data = match loaded.iter().find(|(path, _)| *path == zone.path.as_path()) {
+                Some((_, data)) => data.clone(),
+                None => {
+                    let data = Arc::new(SampleData::load(&zone.path)?);
+                    loaded.push((zone.path.as_path(), data.clone()));
+                    data
+                }
+            };
+
+            kit.push(Zone::new(data, zone)?);
+        }
+
+        Ok(Self { zones: kit })
+    }
+
+    /// the first zone that covers `note`.
+    fn zone(&self, note: MidiNote) -> Option<usize> {
+        self.zones
+            .iter()
+            .position(|zone| (zone.low..=zone.high).contains(&note))
+    }
+}
+
+/// where one note is in its sample.
+#[derive(Clone, Debug)]
+pub struct SamplePlayer {
+    kit: Arc<Kit>,
+    zone: usize,
+    /// in frames of the sample, fractional so it can be pitched.
+    pos: Float,
+    /// frames of the sample per output sample.
+    rate: Float,
+}
+
+impl SamplePlayer {
+    /// starts `note`, `None` if no zone of `kit` covers it.
+    pub fn new(kit: Arc<Kit>, note: MidiNote) -> Option<Self> {
+        let zone = kit.zone(note)?;
+        let Zone { ref data, root, .. } = kit.zones[zone];
+        let rate = data.sample_rate as Float / SAMPLE_RATE as Float
+            * (2.0 as Float).powf((note as Float - root as Float) / 12.0);
+
+        Some(Self {
+            kit,
+            zone,
+            pos: 0.0,
+            rate,
+        })
+    }
+
+    /// changes the pitch to `note` without starting over. false if `note` is played by a different
+    /// zone, it needs a new `SamplePlayer`.
+    pub fn repitch(&mut self, note: MidiNote) -> bool {
+        match SamplePlayer::new(self.kit.clone(), note) {
+            Some(player) if player.zone == self.zone => {
+                self.rate = player.rate;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// one shots play to the end after their note is released.
+    pub fn one_shot(&self) -> bool {
+        self.kit.zones[self.zone].mode == SampleMode::OneShot
+    }
+
+    /// the next sample, or `None` once the end has been played. the loop repeats while `held`.
+    pub fn next(&mut self, held: bool) -> Option<Float> {
+        let zone = &self.kit.zones[self.zone];
+        let samples = &zone.data.samples;
+
+        if let Some((start, end)) = zone.loop_points.filter(|_| held) {
+            let (start, end) = (start as Float, end as Float);
+
+            // a note pitched up far enough steps over the whole loop in one sample.
+            if self.pos >= end {
+                self.pos = start + (self.pos - start) % (end - start);
+            }
+        }
+
+        let i = self.pos as usize;
+        let now = *samples.get(i)?;
+        let next = samples.get(i + 1).copied().unwrap_or(0.0);
+        let frac = self.pos - i as Float;
+
+        self.pos += self.rate;
+
+        Some(now + (next - now) * frac)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a kit of one zone, from 48 to 84 with its root at 60, of a sample that counts up from 0 to
+    /// 7 so each sample says where it was read from.
+    fn kit(mode: SampleMode, loop_points: Option<(usize, usize)>) -> Arc<Kit> {
+        let data = Arc::new(SampleData {
+            samples: (0..8).map(|i| i as Float).collect(),
+            sample_rate: SAMPLE_RATE,
+        });
+        let zone = SampleZone {
+            path: "ramp.wav".into(),
+            root: 60,
+            low: 48,
+            high: 84,
+            mode,
+            loop_points,
+        };
+
+        Arc::new(Kit {
+            zones: vec![Zone::new(data, &zone).unwrap()],
+        })
+    }
+
+    fn play(player: &mut SamplePlayer, held: bool, n: usize) -> Vec<Option<Float>> {
+        (0..n).map(|_| player.next(held)).collect()
+    }
+
+    #[test]
+    fn the_root_note_plays_the_sample_as_is() {
+        let mut player = SamplePlayer::new(kit(SampleMode::Gated, None), 60).unwrap();
+
+        assert_eq!(
+            play(&mut player, true, 3),
+            vec![Some(0.0), Some(1.0), Some(2.0)]
+        );
+    }
+
+    #[test]
+    fn an_octave_up_plays_twice_as_fast() {
+        let mut player = SamplePlayer::new(kit(SampleMode::Gated, None), 72).unwrap();
+
+        assert_eq!(
+            play(&mut player, true, 3),
+            vec![Some(0.0), Some(2.0), Some(4.0)]
+        );
+    }
+
+    #[test]
+    fn notes_outside_every_zone_are_not_played() {
+        assert!(SamplePlayer::new(kit(SampleMode::Gated, None), 47).is_none());
+        assert!(SamplePlayer::new(kit(SampleMode::Gated, None), 85).is_none());
+    }
+
+    #[test]
+    fn one_shots_play_to_the_end() {
+        let mut player = SamplePlayer::new(kit(SampleMode::OneShot, None), 60).unwrap();
+
+        assert!(player.one_shot());
+        assert_eq!(play(&mut player, false, 8).into_iter().flatten().count(), 8);
+        assert_eq!(player.next(false), None);
+    }
+
+    #[test]
+    fn a_held_loop_repeats_until_released() {
+        let mut player = SamplePlayer::new(kit(SampleMode::Gated, Some((2, 6))), 60).unwrap();
+
+        assert!(!player.one_shot());
+        assert!(play(&mut player, true, 32)
+            .into_iter()
+            .all(|sample| sample.is_some_and(|sample| sample < 6.0)));
+
+        // released, it plays on through the end of the sample.
+        let released = play(&mut player, false, 8);
+        assert!(released.contains(&Some(7.0)));
+        assert_eq!(released.last(), Some(&None));
+    }
+
+    #[test]
+    fn a_loop_shorter_than_a_step_stays_in_the_loop() {
+        // two octaves up steps four frames a sample, twice the length of the loop.
+        let mut player = SamplePlayer::new(kit(SampleMode::Gated, Some((2, 4))), 84).unwrap();
+        player.next(true);
+
+        assert!(play(&mut player, true, 16)
+            .into_iter()
+            .all(|sample| sample.is_some_and(|sample| (2.0..4.0).contains(&sample))));
+    }
+
+    #[test]
+    fn empty_loops_are_refused() {
+        let data = Arc::new(SampleData {
+            samples: vec![0.0; 8],
+            sample_rate: SAMPLE_RATE,
+        });
+        let mut zone = SampleZone::drum("empty.wav", 60);
+
+        zone.loop_points = Some((3, 3));
+        assert!(Zone::new(data.clone(), &zone).is_err());
+
+        zone.loop_points = Some((6, 2));
+        assert!(Zone::new(data.clone(), &zone).is_err());
+
+        zone.loop_points = Some((2, 6));
+        assert!(Zone::new(data, &zone).is_ok());
+    }
+}
@@ -1,48 +1,889 @@
+use super::{
+    envelope::Envelope,
+    filter::Svf,
+    fm::FmVoice,
+    lfo::Lfo,
+    osc::{note_freq, Osc, OscSource},
+    sampler::{Kit, SamplePlayer},
+    wavetable::Table,
+};
 use anyhow::Result;
-use synth_8080::{common::Module, midi_osc::MidiOsc, Float};
-use synth_8080_lib::{notes::Note, OscType};
+use std::sync::Arc;
+use synth_8080::{Float, SAMPLE_RATE};
+use tracker_lib::{
+    Adsr, FilterSettings, FmSettings, GlideSettings, MidiNote, ModMatrix, ModRoute, ModSource,
+    ModTarget, NoteTrigger, Sends, StealMode, VelocitySettings, VoiceMode, VoiceSettings, Waveform,
+    N_LFOS,
+};
+
+/// the most voices a track can be set to have.
+const MAX_POLYPHONY: usize = 32;
+
+/// one sounding note, it lives on after it's released until its envelope fades out (or its sample
+/// ends).
+struct Voice {
+    note: MidiNote,
+    /// the pitch right now, it only differs from `target` while gliding.
+    freq: Float,
+    target: Float,
+    /// what `freq` is multiplied by each sample of a glide, and how many samples are left of it.
+    glide_ratio: Float,
+    glide_left: usize,
+    /// when the note was played, counted in notes played on the track.
+    age: u64,
+    /// the volume & cutoff shift (in octaves) from the note's velocity.
+    gain: Float,
+    vel_cutoff: Float,
+    /// true until the note is released.
+    gate: bool,
+    osc: Osc,
+    /// plays instead of `osc` when the track is set to fm.
+    fm: FmVoice,
+    /// set when the note is played by a sampler instead of the oscillator.
+    sample: Option<SamplePlayer>,
+    /// set once the sample has played to its end.
+    finished: bool,
+    env: Envelope,
+    filter: Svf,
+    filter_env: Envelope,
+}
+
+impl Voice {
+    fn new(
+        note: MidiNote,
+        (gain, vel_cutoff): (Float, Float),
+        sample: Option<SamplePlayer>,
+        age: u64,
+    ) -> Self {
+        Self {
+            note,
+            freq: note_freq(note),
+            target: note_freq(note),
+            glide_ratio: 1.0,
+            glide_left: 0,
+            age,
+            gain,
+            vel_cutoff,
+            gate: true,
+            osc: Osc::new(),
+            fm: FmVoice::new(),
+            sample,
+            finished: false,
+            env: Envelope::new(),
+            filter: Svf::new(),
+            filter_env: Envelope::new(),
+        }
+    }
+
+    /// one shot samples ignore the release and play to their end.
+    fn release(&mut self, adsr: &Adsr, filter: &FilterSettings) {
+        self.gate = false;
+
+        if !self.sample.as_ref().is_some_and(SamplePlayer::one_shot) {
+            self.env.release(adsr);
+            self.filter_env.release(&filter.env);
+        }
+    }
+
+    /// takes over as `next` without cutting the sound, the envelopes attack again from wherever
+    /// they are.
+    fn retrigger(&mut self, next: Voice) {
+        self.note = next.note;
+        self.freq = next.freq;
+        self.target = next.target;
+        self.glide_left = 0;
+        self.age = next.age;
+        self.gain = next.gain;
+        self.vel_cutoff = next.vel_cutoff;
+        self.gate = true;
+        self.sample = next.sample;
+        self.finished = false;
+        self.env.retrigger();
+        self.filter_env.retrigger();
+        self.fm.retrigger();
+    }
+
+    /// moves to the pitch of `next` leaving the envelopes be. a sample only starts again if the
+    /// new note is in another zone.
+    fn slide(&mut self, next: Voice) {
+        self.note = next.note;
+        self.freq = next.freq;
+        self.target = next.target;
+        self.glide_left = 0;
+        self.age = next.age;
+
+        if let Some(sample) = self.sample.as_mut() {
+            if !sample.repitch(next.note) {
+                self.sample = next.sample;
+                self.finished = false;
+            }
+        }
+    }
+
+    /// slides the pitch from `from` to the note over `time` seconds, evenly in octaves. samples
+    /// don't glide, only the oscillator.
+    fn glide_from(&mut self, from: Float, time: Float) {
+        let samples = (time * SAMPLE_RATE as Float) as usize;
+
+        if samples == 0 || from <= 0.0 {
+            return;
+        }
+
+        self.glide_ratio = (self.target / from).powf(1.0 / samples as Float);
+        self.glide_left = samples;
+        self.freq = from;
+    }
+
+    fn is_done(&self) -> bool {
+        self.finished || self.env.is_done()
+    }
+
+    /// how loud the voice is right now.
+    fn level(&self) -> Float {
+        self.env.level() * self.gain
+    }
+
+    fn next(
+        &mut self,
+        osc: &OscParams,
+        adsr: &Adsr,
+        filter: &FilterSettings,
+        mods: &Mods,
+    ) -> Float {
+        let pitch = mods.amount(ModTarget::Pitch, Some(&*self));
+        let amp = mods.amount(ModTarget::Amp, Some(&*self));
+        let cutoff_mod = mods.amount(ModTarget::Cutoff, Some(&*self));
+        let position = mods.amount(ModTarget::WavetablePos, Some(&*self));
+
+        if self.glide_left > 0 {
+            self.glide_left -= 1;
+            self.freq = if self.glide_left == 0 {
+                self.target
+            } else {
+                self.freq * self.glide_ratio
+            };
+        }
+
+        let raw = match self.sample.as_mut() {
+            Some(sample) => sample.next(self.gate).unwrap_or_else(|| {
+                self.finished = true;
+                0.0
+            }),
+            None => {
+                let freq = if pitch != 0.0 {
+                    self.freq * (2.0 as Float).powf(pitch / 12.0)
+                } else {
+                    self.freq
+                };
+
+                match osc.fm {
+                    Some(ref fm) => self.fm.next(fm, freq, self.gate),
+                    None => {
+                        let position = (osc.position + position).clamp(0.0, 1.0);
+                        self.osc.next(&osc.source, position, freq)
+                    }
+                }
+            }
+        };
+        let sample = raw * self.env.next(adsr) * self.gain * (1.0 + amp).max(0.0);
+        let octaves = filter.env_amount * self.filter_env.next(&filter.env)
+            + filter.key_track * (self.note as Float - 60.0) / 12.0
+            + self.vel_cutoff
+            + cutoff_mod;
+        let cutoff = filter.cutoff * (2.0 as Float).powf(octaves);
+
+        self.filter
+            .process(sample, filter.mode, cutoff, filter.resonance)
+    }
+}
+
+/// the lfo levels for one sample, and where they & the envelopes are routed.
+struct Mods<'a> {
+    lfos: [Float; N_LFOS],
+    routes: &'a [ModRoute],
+}
+
+impl Mods<'_> {
+    /// everything routed to `target` added up. envelopes are read from `voice`, without one they
+    /// count for nothing.
+    fn amount(&self, target: ModTarget, voice: Option<&Voice>) -> Float {
+        self.routes
+            .iter()
+            .filter(|route| route.target == target)
+            .map(|route| {
+                let level = match route.source {
+                    ModSource::Lfo(i) => self.lfos.get(i).copied().unwrap_or(0.0),
+                    ModSource::AmpEnv => voice.map_or(0.0, |voice| voice.env.level()),
+                    ModSource::FilterEnv => voice.map_or(0.0, |voice| voice.filter_env.level()),
+                };
+
+                level * route.amount
+            })
+            .sum()
+    }
+}
+
+/// what every voice's oscillator plays.
+#[derive(Clone, Debug, Default)]
+struct OscParams {
+    source: OscSource,
+    /// where in a wavetable to read, 0.0 to 1.0.
+    position: Float,
+    /// plays the fm operators instead of `source` when set.
+    fm: Option<FmSettings>,
+}
 
 pub struct Synth {
     pub synth_num: usize,
     pub name: String,
     pub vol: Float,
-    synth: MidiOsc,
+    /// -1.0 (left) to 1.0 (right).
+    pub pan: Float,
+    /// how much goes to each of the `TrackerSynth`'s effects.
+    pub sends: Sends,
+    osc: OscParams,
+    /// plays notes from samples instead of the oscillator when set.
+    sampler: Option<Arc<Kit>>,
+    adsr: Adsr,
+    filter: FilterSettings,
+    velocity: VelocitySettings,
+    voicing: VoiceSettings,
+    glide: GlideSettings,
+    modulation: Arc<ModMatrix>,
+    lfos: [Lfo; N_LFOS],
+    /// what each lfo was at on the last sample.
+    lfo_levels: [Float; N_LFOS],
+    /// the tempo synced lfos follow.
+    bpm: Float,
+    /// how far the modulation matrix moves `pan`, updated every block.
+    pan_mod: Float,
+    voices: Vec<Voice>,
+    /// notes held in mono mode, with their velocity, the last one is the one sounding. never more
+    /// than `MAX_POLYPHONY`, so it doesn't grow on the audio thread.
+    held: Vec<(MidiNote, u8)>,
+    /// how many notes have been played, ages the voices.
+    played: u64,
+    /// the samples made by the last `render_block`.
+    block: Vec<Float>,
 }
 
 impl Synth {
     pub fn new(synth_number: usize) -> Self {
         let name = format!("{synth_number}");
-        let synth = MidiOsc::new(4);
 
         Self {
             synth_num: synth_number,
             name,
             vol: 1.0,
-            synth,
+            pan: 0.0,
+            sends: Sends::default(),
+            osc: OscParams::default(),
+            sampler: None,
+            adsr: Adsr::default(),
+            filter: FilterSettings::default(),
+            velocity: VelocitySettings::default(),
+            voicing: VoiceSettings::default(),
+            glide: GlideSettings::default(),
+            modulation: Arc::default(),
+            lfos: std::array::from_fn(|i| Lfo::new((synth_number * N_LFOS + i + 1) as u32)),
+            lfo_levels: [0.0; N_LFOS],
+            bpm: 110.0,
+            pan_mod: 0.0,
+            voices: Vec::with_capacity(MAX_POLYPHONY),
+            held: Vec::with_capacity(MAX_POLYPHONY),
+            played: 0,
+            block: Vec::new(),
         }
     }
 
-    pub fn get_sample(&mut self) -> Float {
-        self.synth.get_samples()[0].1
+    fn get_sample(&mut self) -> Float {
+        for (i, lfo) in self.lfos.iter_mut().enumerate() {
+            self.lfo_levels[i] = lfo.next(&self.modulation.lfos[i], self.bpm);
+        }
+
+        let (osc, adsr, filter) = (&self.osc, self.adsr, self.filter);
+        let mods = Mods {
+            lfos: self.lfo_levels,
+            routes: &self.modulation.routes,
+        };
+
+        let sample: Float = self
+            .voices
+            .iter_mut()
+            .map(|voice| voice.next(osc, &adsr, &filter, &mods))
+            .sum();
+
+        sample * self.vol
     }
 
-    pub fn play_note(&mut self, note: Note) -> Result<()> {
-        if !self.synth.is_playing(note) {
-            self.synth.play_note(note)?;
-        } else {
-            self.stop_note(note)?;
+    /// renders the next `len` samples, they're read back with `block`.
+    pub fn render_block(&mut self, len: usize) {
+        let mut block = std::mem::take(&mut self.block);
+        block.resize(len, 0.0);
+        block
+            .iter_mut()
+            .for_each(|sample| *sample = self.get_sample());
+        self.block = block;
+
+        self.voices.retain(|voice| !voice.is_done());
+
+        // pan is for the whole track, so envelopes routed to it follow the newest note.
+        let mods = Mods {
+            lfos: self.lfo_levels,
+            routes: &self.modulation.routes,
+        };
+        self.pan_mod = mods.amount(
+            ModTarget::Pan,
+            self.voices.iter().max_by_key(|voice| voice.age),
+        );
+    }
+
+    pub fn block(&self) -> &[Float] {
+        &self.block
+    }
+
+    /// the left & right gains for `pan`. equal power, so a sound is as loud in the center as it is
+    /// hard left.
+    pub fn pan_gains(&self) -> (Float, Float) {
+        let pan = (self.pan + self.pan_mod).clamp(-1.0, 1.0);
+        let angle = (pan + 1.0) * std::f64::consts::FRAC_PI_4 as Float;
+
+        (angle.cos(), angle.sin())
+    }
+
+    fn polyphony(&self) -> usize {
+        self.voicing.polyphony.clamp(1, MAX_POLYPHONY)
+    }
+
+    /// a voice for `note`, `None` when the track is a sampler that has nothing for it.
+    fn new_voice(&mut self, note: MidiNote, velocity: u8) -> Option<Voice> {
+        let sample = match self.sampler.as_ref() {
+            Some(kit) => Some(SamplePlayer::new(kit.clone(), note)?),
+            None => None,
+        };
+        self.played += 1;
+
+        Some(Voice::new(
+            note,
+            self.velocity.apply(velocity),
+            sample,
+            self.played,
+        ))
+    }
+
+    /// plays `note`, `velocity` (0 to 127) goes through the velocity curve. a note that's already
+    /// held is retriggered (or left be when legato), when every voice is in use one is stolen.
+    pub fn play_note(&mut self, note: MidiNote, velocity: u8) -> Result<()> {
+        self.voices.retain(|voice| !voice.is_done());
+
+        match self.voicing.mode {
+            VoiceMode::Poly => self.play_poly(note, velocity),
+            VoiceMode::Mono => self.play_mono(note, velocity),
         }
 
         Ok(())
     }
 
-    pub fn stop_note(&mut self, note: Note) -> Result<()> {
-        self.synth.stop_note(note)?;
+    fn play_poly(&mut self, note: MidiNote, velocity: u8) {
+        if let Some(i) = self
+            .voices
+            .iter()
+            .position(|voice| voice.note == note && voice.gate)
+        {
+            if self.voicing.trigger == NoteTrigger::Retrigger {
+                if let Some(voice) = self.new_voice(note, velocity) {
+                    self.voices[i].retrigger(voice);
+                }
+            }
+
+            return;
+        }
+
+        let Some(mut voice) = self.new_voice(note, velocity) else {
+            return;
+        };
+
+        // a new voice slides from wherever the last note played is.
+        if let Some(last) = self.voices.iter().max_by_key(|voice| voice.age) {
+            if let Some(time) = self.glide.slide_time(last.gate) {
+                voice.glide_from(last.freq, time);
+            }
+        }
+
+        while self.voices.len() >= self.polyphony() {
+            match self.steal() {
+                Some(i) => _ = self.voices.swap_remove(i),
+                None => break,
+            }
+        }
+
+        self.voices.push(voice);
+    }
+
+    /// the voice to cut short to make room, releasing voices go before held ones.
+    fn steal(&self) -> Option<usize> {
+        let releasing = self.voices.iter().any(|voice| !voice.gate);
+
+        self.voices
+            .iter()
+            .enumerate()
+            .filter(|(_, voice)| !(releasing && voice.gate))
+            .min_by(|(_, a), (_, b)| match self.voicing.steal {
+                StealMode::Oldest => a.age.cmp(&b.age),
+                StealMode::Quietest => a.level().total_cmp(&b.level()),
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// the newest voice takes the note, whether it's held or still releasing.
+    fn play_mono(&mut self, note: MidiNote, velocity: u8) {
+        self.held.retain(|(held, _)| *held != note);
+
+        if self.held.len() >= MAX_POLYPHONY {
+            self.held.remove(0);
+        }
+
+        self.held.push((note, velocity));
+
+        let Some(voice) = self.new_voice(note, velocity) else {
+            return;
+        };
+        let legato = self.voicing.trigger == NoteTrigger::Legato;
+        let glide = self.glide;
+
+        match self.voices.iter_mut().max_by_key(|voice| voice.age) {
+            Some(sounding) => {
+                let (from, held) = (sounding.freq, sounding.gate);
+
+                if held && legato {
+                    sounding.slide(voice);
+                } else {
+                    sounding.retrigger(voice);
+                }
+
+                if let Some(time) = glide.slide_time(held) {
+                    sounding.glide_from(from, time);
+                }
+            }
+            None => self.voices.push(voice),
+        }
+    }
+
+    /// releases `note`, it keeps sounding until its release is over. in mono mode the last note
+    /// still held takes over instead.
+    pub fn stop_note(&mut self, note: MidiNote) -> Result<()> {
+        let (adsr, filter) = (self.adsr, self.filter);
+
+        if self.voicing.mode == VoiceMode::Mono {
+            self.held.retain(|(held, _)| *held != note);
+
+            let sounding = self
+                .voices
+                .iter()
+                .position(|voice| voice.note == note && voice.gate);
+
+            if let (Some(i), Some(&(prev, velocity))) = (sounding, self.held.last()) {
+                if let Some(voice) = self.new_voice(prev, velocity) {
+                    let from = self.voices[i].freq;
+
+                    match self.voicing.trigger {
+                        NoteTrigger::Legato => self.voices[i].slide(voice),
+                        NoteTrigger::Retrigger => self.voices[i].retrigger(voice),
+                    }
+
+                    if let Some(time) = self.glide.slide_time(true) {
+                        self.voices[i].glide_from(from, time);
+                    }
+
+                    return Ok(());
+                }
+            }
+        }
+
+        self.voices
+            .iter_mut()
+            .filter(|voice| voice.note == note && voice.gate)
+            .for_each(|voice| voice.release(&adsr, &filter));
 
         Ok(())
     }
 
-    pub fn set_waveform(&mut self, waveform: OscType) {
-        self.synth.set_wave_form(waveform);
+    /// notes that are held when switching to mono are released, so they fade out.
+    pub fn set_voices(&mut self, voicing: VoiceSettings) {
+        if voicing.mode != self.voicing.mode {
+            let (adsr, filter) = (self.adsr, self.filter);
+
+            self.held.clear();
+            self.voices
+                .iter_mut()
+                .filter(|voice| voice.gate)
+                .for_each(|voice| voice.release(&adsr, &filter));
+        }
+
+        self.voicing = voicing;
+    }
+
+    /// plays notes from `kit`, or from the oscillator again when `None`. notes that are already
+    /// sounding carry on with what they started with. the kit that was replaced is returned.
+    pub fn set_sampler(&mut self, kit: Option<Arc<Kit>>) -> Option<Arc<Kit>> {
+        std::mem::replace(&mut self.sampler, kit)
+    }
+
+    /// `None` goes back to the oscillator. the track's envelope & filter still shape the fm voice.
+    pub fn set_fm(&mut self, fm: Option<FmSettings>) {
+        self.osc.fm = fm;
+    }
+
+    /// returns the source that was replaced.
+    pub fn set_waveform(&mut self, waveform: Waveform) -> OscSource {
+        std::mem::replace(&mut self.osc.source, OscSource::Basic(waveform))
+    }
+
+    /// returns the source that was replaced.
+    pub fn set_wavetable(&mut self, table: Arc<Table>) -> OscSource {
+        std::mem::replace(&mut self.osc.source, OscSource::Table(table))
+    }
+
+    /// where in the wavetable to read, 0.0 (the first frame) to 1.0 (the last).
+    pub fn set_wavetable_pos(&mut self, position: Float) {
+        self.osc.position = position.clamp(0.0, 1.0);
+    }
+
+    pub fn set_envelope(&mut self, adsr: Adsr) {
+        self.adsr = adsr;
+    }
+
+    pub fn set_filter(&mut self, filter: FilterSettings) {
+        self.filter = filter;
+    }
+
+    pub fn set_velocity(&mut self, velocity: VelocitySettings) {
+        self.velocity = velocity;
+    }
+
+    /// returns the matrix that was replaced.
+    pub fn set_modulation(&mut self, modulation: Arc<ModMatrix>) -> Arc<ModMatrix> {
+        std::mem::replace(&mut self.modulation, modulation)
+    }
+
+    /// the tempo, in bpm, that synced lfos follow.
+    pub fn set_tempo(&mut self, bpm: Float) {
+        self.bpm = bpm;
+    }
+
+    pub fn set_glide(&mut self, glide: GlideSettings) {
+        self.glide = glide;
+    }
+
+    /// how long notes slide for, leaving the glide mode be. 0.0 turns glide off.
+    pub fn set_glide_time(&mut self, time: Float) {
+        self.glide.time = time.max(0.0);
+    }
+
+    pub fn filter(&self) -> FilterSettings {
+        self.filter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracker_lib::{GlideMode, LfoShape};
+
+    fn synth(voicing: VoiceSettings) -> Synth {
+        let mut synth = Synth::new(0);
+        synth.set_voices(voicing);
+
+        synth
+    }
+
+    fn poly(polyphony: usize, steal: StealMode) -> VoiceSettings {
+        VoiceSettings {
+            polyphony,
+            steal,
+            ..VoiceSettings::default()
+        }
+    }
+
+    fn mono(trigger: NoteTrigger) -> VoiceSettings {
+        VoiceSettings {
+            mode: VoiceMode::Mono,
+            trigger,
+            ..VoiceSettings::default()
+        }
+    }
+
+    /// an envelope that's at full level from the first sample, then falls over a second.
+    fn falling() -> Adsr {
+        Adsr {
+            attack: 0.0,
+            decay: 1.0,
+            sustain: 0.0,
+            release: 0.1,
+        }
+    }
+
+    /// every note with a voice, held or releasing, lowest first.
+    fn sounding(synth: &Synth) -> Vec<MidiNote> {
+        let mut notes: Vec<MidiNote> = synth.voices.iter().map(|voice| voice.note).collect();
+        notes.sort();
+
+        notes
+    }
+
+    fn held(synth: &Synth) -> Vec<MidiNote> {
+        let mut notes: Vec<MidiNote> = synth
+            .voices
+            .iter()
+            .filter(|voice| voice.gate)
+            .map(|voice| voice.note)
+            .collect();
+        notes.sort();
+
+        notes
+    }
+
+    #[test]
+    fn replaying_a_held_note_retriggers_it() {
+        let mut synth = synth(VoiceSettings::default());
+
+        synth.play_note(60, 100).unwrap();
+        synth.play_note(60, 100).unwrap();
+
+        assert_eq!(held(&synth), vec![60]);
+        assert_eq!(synth.voices.len(), 1);
+        assert_eq!(synth.voices[0].age, 2);
+    }
+
+    #[test]
+    fn legato_leaves_a_held_note_be() {
+        let mut synth = synth(VoiceSettings {
+            trigger: NoteTrigger::Legato,
+            ..VoiceSettings::default()
+        });
+
+        synth.play_note(60, 100).unwrap();
+        synth.play_note(60, 100).unwrap();
+
+        assert_eq!(held(&synth), vec![60]);
+        assert_eq!(synth.voices[0].age, 1);
+    }
+
+    #[test]
+    fn oldest_voice_is_stolen() {
+        let mut synth = synth(poly(2, StealMode::Oldest));
+
+        synth.play_note(60, 100).unwrap();
+        synth.play_note(62, 100).unwrap();
+        synth.play_note(64, 100).unwrap();
+
+        assert_eq!(sounding(&synth), vec![62, 64]);
+    }
+
+    #[test]
+    fn quietest_voice_is_stolen() {
+        let mut synth = synth(poly(2, StealMode::Quietest));
+        synth.set_envelope(falling());
+
+        synth.play_note(60, 127).unwrap();
+        synth.play_note(62, 10).unwrap();
+        synth.render_block(16);
+        synth.play_note(64, 127).unwrap();
+
+        assert_eq!(sounding(&synth), vec![60, 64]);
+    }
+
+    #[test]
+    fn releasing_voices_are_stolen_first() {
+        let mut synth = synth(poly(2, StealMode::Oldest));
+
+        synth.play_note(60, 100).unwrap();
+        synth.play_note(62, 100).unwrap();
+        synth.stop_note(62).unwrap();
+        synth.play_note(64, 100).unwrap();
+
+        assert_eq!(sounding(&synth), vec![60, 64]);
+        assert_eq!(held(&synth), vec![60, 64]);
+    }
+
+    #[test]
+    fn polyphony_is_never_exceeded() {
+        let mut synth = synth(poly(3, StealMode::Oldest));
+
+        for note in 48..72 {
+            synth.play_note(note, 100).unwrap();
+
+            if note % 2 == 0 {
+                synth.stop_note(note).unwrap();
+            }
+
+            assert!(synth.voices.len() <= 3);
+        }
+    }
+
+    #[test]
+    fn mono_plays_the_last_note_held() {
+        let mut synth = synth(mono(NoteTrigger::Retrigger));
+
+        synth.play_note(60, 100).unwrap();
+        synth.play_note(64, 100).unwrap();
+        synth.play_note(67, 100).unwrap();
+        assert_eq!(sounding(&synth), vec![67]);
+
+        synth.stop_note(67).unwrap();
+        assert_eq!(held(&synth), vec![64]);
+
+        // letting go of a note that isn't sounding changes nothing.
+        synth.stop_note(60).unwrap();
+        assert_eq!(held(&synth), vec![64]);
+
+        synth.stop_note(64).unwrap();
+        assert_eq!(sounding(&synth), vec![64]);
+        assert!(held(&synth).is_empty());
+    }
+
+    #[test]
+    fn mono_remembers_at_most_max_polyphony_notes() {
+        let mut synth = synth(mono(NoteTrigger::Retrigger));
+        let capacity = synth.held.capacity();
+
+        for note in 0..(MAX_POLYPHONY * 2) as MidiNote {
+            synth.play_note(note, 100).unwrap();
+        }
+
+        assert_eq!(synth.held.len(), MAX_POLYPHONY);
+        assert_eq!(synth.held.capacity(), capacity);
+    }
+
+    #[test]
+    fn mono_retrigger_restarts_the_envelope() {
+        let mut synth = synth(mono(NoteTrigger::Retrigger));
+        synth.set_envelope(falling());
+
+        synth.play_note(60, 100).unwrap();
+        synth.render_block(SAMPLE_RATE as usize / 2);
+        synth.play_note(62, 100).unwrap();
+        synth.render_block(1);
+
+        assert_eq!(synth.voices.len(), 1);
+        assert_eq!(synth.voices[0].env.level(), 1.0);
+    }
+
+    #[test]
+    fn mono_legato_keeps_the_envelope_going() {
+        let mut synth = synth(mono(NoteTrigger::Legato));
+        synth.set_envelope(falling());
+
+        synth.play_note(60, 100).unwrap();
+        synth.render_block(SAMPLE_RATE as usize / 2);
+        let level = synth.voices[0].env.level();
+        synth.play_note(62, 100).unwrap();
+        synth.render_block(1);
+
+        assert_eq!(sounding(&synth), vec![62]);
+        assert!(synth.voices[0].env.level() < level);
+    }
+
+    #[test]
+    fn glide_slides_to_the_new_note() {
+        let mut synth = synth(mono(NoteTrigger::Legato));
+        synth.set_glide(GlideSettings {
+            time: 0.1,
+            mode: GlideMode::Always,
+        });
+
+        synth.play_note(60, 100).unwrap();
+        synth.play_note(72, 100).unwrap();
+        synth.render_block(SAMPLE_RATE as usize / 20);
+
+        let freq = synth.voices[0].freq;
+        assert!(freq > note_freq(60) && freq < note_freq(72));
+
+        synth.render_block(SAMPLE_RATE as usize / 10);
+        assert_eq!(synth.voices[0].freq, note_freq(72));
+    }
+
+    #[test]
+    fn legato_glide_skips_detached_notes() {
+        let mut synth = synth(mono(NoteTrigger::Retrigger));
+        synth.set_glide(GlideSettings {
+            time: 0.1,
+            mode: GlideMode::Legato,
+        });
+
+        synth.play_note(60, 100).unwrap();
+        synth.stop_note(60).unwrap();
+        synth.play_note(72, 100).unwrap();
+
+        assert_eq!(synth.voices[0].freq, note_freq(72));
+        assert_eq!(synth.voices[0].glide_left, 0);
+    }
+
+    #[test]
+    fn going_mono_releases_held_notes() {
+        let mut synth = synth(VoiceSettings::default());
+
+        synth.play_note(60, 100).unwrap();
+        synth.play_note(64, 100).unwrap();
+        synth.set_voices(mono(NoteTrigger::Retrigger));
+
+        assert!(held(&synth).is_empty());
+    }
+
+    fn routed(source: ModSource, target: ModTarget, amount: Float) -> ModMatrix {
+        let mut modulation = ModMatrix::default();
+        modulation.routes.push(ModRoute {
+            source,
+            target,
+            amount,
+        });
+
+        modulation
+    }
+
+    #[test]
+    fn lfo_routed_to_amp_scales_the_voice() {
+        let mut synth = synth(VoiceSettings::default());
+        let mut modulation = routed(ModSource::Lfo(0), ModTarget::Amp, -1.0);
+        modulation.lfos[0].shape = LfoShape::Square;
+        synth.set_modulation(Arc::new(modulation));
+
+        // a 1 Hz square sits at its top for the first half second, so the voice is silenced.
+        synth.play_note(60, 100).unwrap();
+        synth.render_block(64);
+
+        assert!(synth.block().iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn envelope_routed_to_pan_follows_the_newest_note() {
+        let mut synth = synth(VoiceSettings::default());
+        synth.set_envelope(falling());
+        synth.set_modulation(Arc::new(routed(ModSource::AmpEnv, ModTarget::Pan, 1.0)));
+
+        synth.render_block(1);
+        assert_eq!(synth.pan_mod, 0.0);
+
+        synth.play_note(60, 100).unwrap();
+        synth.render_block(1);
+        assert!(synth.pan_mod > 0.9);
+    }
+
+    #[test]
+    fn fm_only_hears_its_carriers() {
+        let mut synth = synth(VoiceSettings::default());
+        let mut fm = FmSettings::default();
+        fm.operators[0].level = 0.0;
+        synth.set_fm(Some(fm));
+
+        // the modulator is running, but with a silent carrier there's nothing to hear.
+        synth.play_note(60, 100).unwrap();
+        synth.render_block(64);
+        assert!(synth.block().iter().all(|sample| *sample == 0.0));
+
+        fm.operators[0].level = 1.0;
+        synth.set_fm(Some(fm));
+        synth.render_block(64);
+        assert!(synth.block().iter().any(|sample| *sample != 0.0));
     }
 }
@@ -0,0 +1,176 @@
+use anyhow::{bail, Result};
+use hound::{SampleFormat, WavReader};
+use std::path::Path;
+use synth_8080::Float;
+use tracing::*;
+
+/// the most frames a wavetable can have.
+const MAX_FRAMES: usize = 256;
+
+/// one or more single cycle waveforms, the oscillator reads between them by position.
+#[derive(Clone, Debug)]
+pub struct Table {
+    frames: Vec<Vec<Float>>,
+}
+
+impl Table {
+    /// makes a table from frames that are all the same, non zero, length.
+    pub fn new(frames: Vec<Vec<Float>>) -> Result<Self> {
+        let Some(len) = frames.first().map(Vec::len) else {
+            bail!("a wavetable needs at least one frame.");
+        };
+
+        if len < 2 {
+            bail!("wavetable frames need at least two samples.");
+        }
+
+        if frames.iter().any(|frame| frame.len() != len) {
+            bail!("every frame of a wavetable must be the same length.");
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// loads a wav file of `frames` single cycles, split evenly. multi-channel files are mixed
+    /// down.
+    pub fn load(path: impl AsRef<Path>, frames: usize) -> Result<Self> {
+        let path = path.as_ref();
+
+        if frames == 0 || frames > MAX_FRAMES {
+            bail!("a wavetable has from 1 to {MAX_FRAMES} frames, not {frames}.");
+        }
+
+        let mut reader = WavReader::open(path)?;
+        let spec = reader.spec();
+        let channels = spec.channels.max(1) as usize;
+
+        let interleaved: Vec<Float> = match spec.sample_format {
+            SampleFormat::Float => reader
+                .samples::<f32>()
+                .map(|sample| sample.map(|sample| sample as Float))
+                .collect::<Result<_, _>>()?,
+            SampleFormat::Int => {
+                let max = (1_i64 << (spec.bits_per_sample - 1)) as Float;
+
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as Float / max))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+
+        let samples: Vec<Float> = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<Float>() / channels as Float)
+            .collect();
+
+        if samples.is_empty() || samples.len() % frames != 0 {
+            bail!(
+                "{} is {} samples long, it can't be split into {frames} frames.",
+                path.display(),
+                samples.len()
+            );
+        }
+
+        let frames: Vec<Vec<Float>> = samples
+            .chunks(samples.len() / frames)
+            .map(<[Float]>::to_vec)
+            .collect();
+
+        info!(
+            "loaded a wavetable of {} frame(s) from {}",
+            frames.len(),
+            path.display()
+        );
+
+        Self::new(frames).map(Self::normalized)
+    }
+
+    /// scales the table so its loudest sample is at full scale.
+    fn normalized(mut self) -> Self {
+        let peak = self
+            .frames
+            .iter()
+            .flatten()
+            .fold(0.0 as Float, |peak, sample| peak.max(sample.abs()));
+
+        if peak > 0.0 {
+            self.frames
+                .iter_mut()
+                .flatten()
+                .for_each(|sample| *sample /= peak);
+        }
+
+        self
+    }
+
+    pub fn n_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    fn read_frame(frame: &[Float], phase: Float) -> Float {
+        let pos = phase * frame.len() as Float;
+        let i = pos as usize % frame.len();
+        let frac = pos - pos.floor();
+        let next = frame[(i + 1) % frame.len()];
+
+        frame[i] + (next - frame[i]) * frac
+    }
+
+    /// the table at `phase` (0.0 to 1.0 through the cycle) and `position` (0.0 is the first
+    /// frame, 1.0 the last), interpolating between samples and frames.
+    pub fn sample(&self, phase: Float, position: Float) -> Float {
+        let pos = position.clamp(0.0, 1.0) * (self.frames.len() - 1) as Float;
+        let i = pos as usize;
+        let frac = pos - i as Float;
+        let now = Self::read_frame(&self.frames[i], phase);
+
+        match self.frames.get(i + 1) {
+            Some(next) if frac > 0.0 => now + (Self::read_frame(next, phase) - now) * frac,
+            _ => now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hound::{WavSpec, WavWriter};
+    use std::path::PathBuf;
+
+    /// a mono wav of `len` samples of a rising ramp, written to the temp dir as `name`.
+    fn ramp_wav(name: &str, len: usize) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 48_000,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+
+        for i in 0..len {
+            writer.write_sample(i as f32 / len as f32).unwrap();
+        }
+
+        writer.finalize().unwrap();
+
+        path
+    }
+
+    #[test]
+    fn a_file_is_split_into_the_frames_asked_for() {
+        let path = ramp_wav("rusty-tracker-four-frames.wav", 64);
+
+        assert_eq!(Table::load(&path, 1).unwrap().n_frames(), 1);
+        assert_eq!(Table::load(&path, 4).unwrap().n_frames(), 4);
+    }
+
+    #[test]
+    fn frames_that_dont_divide_the_file_are_refused() {
+        let path = ramp_wav("rusty-tracker-uneven-frames.wav", 64);
+
+        assert!(Table::load(&path, 3).is_err());
+        assert!(Table::load(&path, 0).is_err());
+    }
+}